@@ -0,0 +1,106 @@
+use nalgebra::{Matrix3, Point2, Rotation2, Vector2};
+use trunk_template::sim::wrapped_delta;
+
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+//fraction of the way to the target covered per ms, for the position and the zoom
+const FOLLOW_RATE: f64 = 0.004;
+const ZOOM_RATE: f64 = 0.01;
+//trauma lost per ms, a full shake lasts a bit more than a second
+const TRAUMA_DECAY: f64 = 0.0008;
+//shake at full trauma, in world units and radians
+const MAX_SHAKE_OFFSET: f64 = 40.0;
+const MAX_SHAKE_ANGLE: f64 = 0.05;
+
+//what part of the world is looked at, applied by the `view` uniform of the shaders
+#[derive(Debug, Clone)]
+pub struct Camera {
+    //center of the view, always inside the map
+    pos: Point2<f64>,
+    //zoom shown right now, it eases towards `zoom`
+    scale: f64,
+    zoom: f64,
+    //when off the camera goes back to the center of the map
+    pub follow: bool,
+    //0 to 1, the shake grows with its square so small hits barely move the screen
+    trauma: f64,
+    time: f64,
+    shake: Vector2<f64>,
+    angle: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            pos: Point2::origin(),
+            scale: 1.0,
+            zoom: 1.0,
+            follow: false,
+            trauma: 0.0,
+            time: 0.0,
+            shake: Vector2::zeros(),
+            angle: 0.0,
+        }
+    }
+}
+
+//smooth noise from -1 to 1, a few sines are enough to not look periodic
+fn noise(time: f64, seed: f64) -> f64 {
+    ((time * 0.031 + seed).sin() + (time * 0.057 + seed * 2.0).sin() * 0.5) / 1.5
+}
+
+impl Camera {
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    //multiplies the zoom, clamped between MIN_ZOOM and MAX_ZOOM
+    pub fn zoom_by(&mut self, factor: f64) {
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    pub fn add_trauma(&mut self, trauma: f64) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+
+    //delta is the real time since the last frame, target is what to follow
+    pub fn update(&mut self, delta: f64, target: Option<Point2<f64>>, map_dim: Vector2<f64>) {
+        let rate = |x: f64| 1.0 - (1.0 - x).powf(delta);
+
+        //keeps looking where it was while there is no ship
+        let target = if self.follow {
+            target.unwrap_or(self.pos)
+        } else {
+            Point2::origin()
+        };
+        //the shortest way, across the edge of the map if needed
+        self.pos += wrapped_delta(self.pos, target, map_dim) * rate(FOLLOW_RATE);
+        let half = map_dim / 2.0;
+        for axis in 0..2 {
+            if self.pos[axis].abs() > half[axis] {
+                self.pos[axis] -= self.pos[axis].signum() * map_dim[axis];
+            }
+        }
+
+        self.scale += (self.zoom - self.scale) * rate(ZOOM_RATE);
+
+        self.trauma = (self.trauma - TRAUMA_DECAY * delta).max(0.0);
+        self.time += delta;
+        let shake = self.trauma * self.trauma;
+        self.shake =
+            Vector2::new(noise(self.time, 0.0), noise(self.time, 10.0)) * shake * MAX_SHAKE_OFFSET;
+        self.angle = noise(self.time, 20.0) * shake * MAX_SHAKE_ANGLE;
+    }
+
+    //world to view coordinates, the camera ends up at the origin
+    pub fn view(&self) -> Matrix3<f64> {
+        let rotation = Rotation2::new(-self.angle).to_homogeneous();
+        let translation = Matrix3::new_translation(&-(self.pos.coords + self.shake));
+        Matrix3::new_nonuniform_scaling(&Vector2::repeat(self.scale)) * rotation * translation
+    }
+
+    //view to world coordinates, to know where the mouse points
+    pub fn inverse_view(&self) -> Matrix3<f64> {
+        self.view().try_inverse().unwrap_or_else(Matrix3::identity)
+    }
+}
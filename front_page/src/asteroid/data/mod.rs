@@ -1,15 +0,0 @@
-pub fn get_ship() -> &'static str {
-    include_str!("ship.json")
-}
-
-pub fn get_bullet() -> &'static str {
-    include_str!("bullet.json")
-}
-
-pub fn get_asteroid() -> &'static str {
-    include_str!("asteroid.json")
-}
-
-// pub fn get_teste() -> &'static str {
-//     include_str!("teste.json")
-// }
\ No newline at end of file
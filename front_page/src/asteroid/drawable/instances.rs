@@ -0,0 +1,91 @@
+use std::mem::size_of;
+
+use crate::asteroid::shader::ShaderError;
+
+use super::*;
+use wasm_bindgen::JsCast;
+
+//offset (2), rotation, scale, color (3)
+pub const INSTANCE_LEN: usize = 7;
+pub const INSTANCE_STRIDE: i32 = (INSTANCE_LEN * size_of::<f32>()) as i32;
+
+//one copy of a model on the screen, everything the vertex shader needs besides the model
+#[derive(Debug, Clone, Copy)]
+pub struct Instance {
+    pub offset: Point2<f64>,
+    pub rotation: f64,
+    pub scale: f64,
+    pub color: Vector3<f64>,
+}
+
+//the instances of the model being drawn, rewritten for every model each frame
+//without ANGLE_instanced_arrays the instances are still kept here, but drawn one by one
+pub struct InstanceBuffer {
+    ext: Option<AngleInstancedArrays>,
+    gl_buf: GpuBuffer,
+    //floats the gpu buffer holds
+    capacity: usize,
+    data: Vec<f32>,
+}
+
+impl InstanceBuffer {
+    pub fn new(gl: &WebGlRenderingContext) -> Result<Self, ShaderError> {
+        let ext = gl
+            .get_extension("ANGLE_instanced_arrays")
+            .ok()
+            .flatten()
+            .map(|x| x.unchecked_into::<AngleInstancedArrays>());
+        if ext.is_none() {
+            log::warn!("ANGLE_instanced_arrays is not supported, every copy is a draw call");
+        }
+
+        Ok(InstanceBuffer {
+            ext,
+            gl_buf: GpuBuffer::new(gl)?,
+            capacity: 0,
+            data: Vec::new(),
+        })
+    }
+
+    pub fn ext(&self) -> Option<&AngleInstancedArrays> {
+        self.ext.as_ref()
+    }
+
+    //sends the instances to the gpu, the buffer is left bound to ARRAY_BUFFER
+    pub fn upload(&mut self, gl: &WebGlRenderingContext, instances: &[Instance]) {
+        self.data.clear();
+        for instance in instances {
+            self.data.extend(
+                [
+                    instance.offset.x,
+                    instance.offset.y,
+                    instance.rotation,
+                    instance.scale,
+                    instance.color.x,
+                    instance.color.y,
+                    instance.color.z,
+                ]
+                .map(|x| x as f32),
+            );
+        }
+
+        self.gl_buf.bind(WebGlRenderingContext::ARRAY_BUFFER);
+        unsafe {
+            let data = js_sys::Float32Array::view(&self.data);
+            if self.data.len() > self.capacity {
+                self.capacity = self.data.len();
+                gl.buffer_data_with_array_buffer_view(
+                    WebGlRenderingContext::ARRAY_BUFFER,
+                    &data,
+                    WebGlRenderingContext::DYNAMIC_DRAW,
+                );
+            } else {
+                gl.buffer_sub_data_with_i32_and_array_buffer_view(
+                    WebGlRenderingContext::ARRAY_BUFFER,
+                    0,
+                    &data,
+                );
+            }
+        }
+    }
+}
@@ -1,254 +1,136 @@
+use std::mem::size_of;
 use std::rc::Rc;
 
-use crate::asteroid::shader;
+use crate::asteroid::shader::{self, ShaderError, ShaderProgram};
 
 use super::*;
-use na::{Matrix2x3, Matrix2xX, Point2, Vector3};
-use serde::Deserialize;
-
-#[derive(Deserialize, Debug, Clone)]
-pub enum DrawMode {
-    LineLoop,
-    TriangleFan,
-    TriangleStrip,
-    Lines,
-    Triangles,
-    Points,
+use na::Matrix3;
+use trunk_template::sim::{DrawMode, Object};
+
+//locations in the object program, the same for every model
+#[derive(Debug, Clone, Copy)]
+struct ObjectAttributes {
+    vert_position: u32,
+    //the rest change per instance
+    offset: u32,
+    rot: u32,
+    scale: u32,
+    color: u32,
 }
 
-impl Default for DrawMode {
-    fn default() -> Self {
-        Self::LineLoop
+impl ObjectAttributes {
+    //location, size and position in an instance, in the same order as `Instance` is written
+    fn per_instance(&self) -> [(u32, i32, i32); 4] {
+        [
+            (self.offset, 2, 0),
+            (self.rot, 1, 2),
+            (self.scale, 1, 3),
+            (self.color, 3, 4),
+        ]
     }
-}
 
-#[derive(Deserialize, Debug)]
-pub struct Object {
-    dimentions: Vector2<f64>,
-    hit_box: Matrix2xX<f64>,
-    hit_box_edge: Matrix2xX<usize>,
-    hit_box_obj: Vec<Vec<usize>>,
-    lst_vec_point: Vec<Point2<f64>>,
-    lst_hit_box: Vec<Matrix2x3<f64>>,
-    #[serde(default)]
-    scale: f64,
-    #[serde(default)]
-    buff_loc: Option<usize>,
-    #[serde(default)]
-    draw_mode: DrawMode,
+    fn new(prg: &ShaderProgram) -> Result<Self, ShaderError> {
+        Ok(ObjectAttributes {
+            vert_position: prg.attribute("vert_position")?,
+            offset: prg.attribute("offset")?,
+            rot: prg.attribute("rot")?,
+            scale: prg.attribute("scale")?,
+            color: prg.attribute("color")?,
+        })
+    }
 }
 
-//afim de evitar que o programa seja compilado varias vezes e o mesmo espaço de memoria seja alocado na placa de video
-//cria-se uma variavel estatica que vai ser compartilhada entre todas as instancias de ObjectDrawable
-static mut GL_PRG: Option<Rc<WebGlProgram>> = None;
-static mut GL_BUF: Option<Vec<Option<Rc<WebGlBuffer>>>> = None;
-
+//gpu side of an `Object`, one is shared by every entity of the same model
 #[derive(Debug, Clone)]
 pub struct ObjectDrawable {
-    pub lst_hit_box: Vec<Matrix2x3<f64>>,
     pub dimentions: Vector2<f64>,
-    pub scale: f64,
-    prg: Rc<WebGlProgram>,
-    gl_buf: Rc<WebGlBuffer>,
+    prg: Rc<ShaderProgram>,
+    attributes: ObjectAttributes,
+    gl_buf: Rc<GpuBuffer>,
     vertex_count: i32,
     draw_mode: DrawMode,
-    pub hit_box: Matrix2xX<f64>,
-    pub hit_box_edge: Matrix2xX<usize>,
-    pub hit_box_obj: Vec<Vec<usize>>,
 }
 
-impl Object {
-    fn init_web_gl_program(gl: &WebGlRenderingContext) -> Rc<WebGlProgram> {
-        if let Some(prg) = unsafe { GL_PRG.clone() } {
-            return prg.clone();
-        }
-
-        let vertex_shader = gl
-            .create_shader(WebGlRenderingContext::VERTEX_SHADER)
-            .unwrap();
-        gl.shader_source(&vertex_shader, shader::get_vertex_shader());
-        gl.compile_shader(&vertex_shader);
-        if !gl
-            .get_shader_parameter(&vertex_shader, WebGlRenderingContext::COMPILE_STATUS)
-            .as_bool()
-            .unwrap()
-        {
-            panic!(
-                "Erro ao compilar o vertex shader: {}",
-                gl.get_shader_info_log(&vertex_shader).unwrap()
-            );
-        }
-
-        let fragment_shader = gl
-            .create_shader(WebGlRenderingContext::FRAGMENT_SHADER)
-            .unwrap();
-        gl.shader_source(&fragment_shader, shader::get_fragment_shader());
-        gl.compile_shader(&fragment_shader);
-        if !gl
-            .get_shader_parameter(&fragment_shader, WebGlRenderingContext::COMPILE_STATUS)
-            .as_bool()
-            .unwrap()
-        {
-            panic!(
-                "Erro ao compilar o fragment shader: {}",
-                gl.get_shader_info_log(&fragment_shader).unwrap()
-            );
-        }
-        let prg = gl.create_program().unwrap();
-        gl.attach_shader(&prg, &vertex_shader);
-        gl.attach_shader(&prg, &fragment_shader);
-        gl.link_program(&prg);
-        if !gl
-            .get_program_parameter(&prg, WebGlRenderingContext::LINK_STATUS)
-            .as_bool()
-            .unwrap()
-        {
-            panic!(
-                "Erro ao linkar o programa: {}",
-                gl.get_program_info_log(&prg).unwrap()
-            );
-        }
-
-        unsafe {
-            GL_PRG = Some(Rc::new(prg));
-        }
-
-        Object::init_web_gl_program(gl)
-    }
-
-    fn create_gl_buf(&self, gl: &WebGlRenderingContext) -> Rc<WebGlBuffer> {
-        let points: Vec<f32> = self
-            .lst_vec_point
-            .iter()
-            .flat_map(|point| [point.x as f32, point.y as f32])
-            .collect();
-
-        let points_buff = gl.create_buffer();
-        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, points_buff.as_ref());
-        unsafe {
-            let points = js_sys::Float32Array::view(&points);
-            gl.buffer_data_with_array_buffer_view(
-                WebGlRenderingContext::ARRAY_BUFFER,
-                &points,
-                WebGlRenderingContext::STATIC_DRAW,
-            );
-        }
-
-        Rc::new(points_buff.unwrap())
+impl ObjectDrawable {
+    //how the world is mapped to the canvas, kept by the program until the next call
+    pub fn set_view(&self, gl: &WebGlRenderingContext, projection: Vector2<f64>, pixel_ratio: f64) {
+        self.prg.use_program(gl);
+        gl.uniform2f(
+            self.prg.uniform("projection"),
+            projection.x as f32,
+            projection.y as f32,
+        );
+        gl.uniform1f(self.prg.uniform("pixel_ratio"), pixel_ratio as f32);
     }
-    fn init_buff(&self, gl: &WebGlRenderingContext) -> Rc<WebGlBuffer> {
-        //Se n tiver index cria-se um novo buffer sempre
-        if let None = self.buff_loc {
-            return self.create_gl_buf(gl);
-        }
-
-        //Caso contrario pesquisa p/ saber se já existe um buffer criado
-        let index = self.buff_loc.unwrap();
-
-        //recupera a lista de buffers como mutável
-        let gl_buf = unsafe {
-            if GL_BUF.is_none() {
-                GL_BUF = Some(Vec::new());
-            }
-            GL_BUF.as_mut().unwrap()
-        };
-
-        //Se o Vetor n tiver o tamanho do index, vai expandir o vetor até o index
-        if index >= gl_buf.len() {
-            gl_buf.resize(index + 1, None);
-        }
 
-        //Se n existir cria-se um novo buff e guarda no index
-        if let None = gl_buf[index] {
-            let points_buff = self.create_gl_buf(gl);
-            gl_buf[index] = Some(points_buff);
-        }
-
-        //retorna o buffer
-        gl_buf[index].as_ref().unwrap().clone()
+    pub fn set_camera(&self, gl: &WebGlRenderingContext, view: &Matrix3<f64>) {
+        self.prg.use_program(gl);
+        gl.uniform_matrix3fv_with_f32_array(
+            self.prg.uniform("view"),
+            false,
+            view.cast::<f32>().as_slice(),
+        );
     }
 
-    pub fn load_gl(self, gl: &WebGlRenderingContext) -> ObjectDrawable {
-        let prg = Object::init_web_gl_program(gl);
-        let gl_buf = self.init_buff(gl);
-
-        ObjectDrawable {
-            lst_hit_box: self.lst_hit_box,
-            dimentions: self.dimentions,
-            scale: self.scale,
+    //the program is shared by every model and the vertices by every drawable of the same model
+    pub fn load_gl(
+        object: &Object,
+        resources: &mut GpuResources,
+    ) -> Result<ObjectDrawable, ShaderError> {
+        let prg = resources.program(
+            "object",
+            shader::get_vertex_shader(),
+            shader::get_fragment_shader(),
+        )?;
+        let gl_buf = resources.buffer(object.model.name(), || {
+            object
+                .lst_vec_point
+                .iter()
+                .flat_map(|point| [point.x as f32, point.y as f32])
+                .collect()
+        })?;
+
+        Ok(ObjectDrawable {
+            dimentions: object.dimentions,
+            attributes: ObjectAttributes::new(&prg)?,
             prg,
             gl_buf,
-            vertex_count: self.lst_vec_point.len() as i32,
-            draw_mode: self.draw_mode,
-            hit_box: self.hit_box,
-            hit_box_edge: self.hit_box_edge,
-            hit_box_obj: self.hit_box_obj,
-        }
-    }
-}
-
-impl TryFrom<&str> for Object {
-    type Error = serde_json::Error;
-    fn try_from(data: &str) -> Result<Self, Self::Error> {
-        let ship: Object = serde_json::from_str(data)?;
-        Ok(ship)
+            vertex_count: object.lst_vec_point.len() as i32,
+            draw_mode: object.draw_mode,
+        })
     }
 }
 
 impl ObjectDrawable {
-    pub fn dimentions(&self) -> Vector2<f64> {
-        self.dimentions * self.scale
-    }
-}
-
-impl Drawable for ObjectDrawable {
-    fn draw(
+    //draws every instance of the model, in a single call when instancing is supported
+    pub fn draw(
         &self,
         gl: &WebGlRenderingContext,
-        offset: Point2<f64>,
-        rotation: f64,
-        color: Vector3<f64>,
-    ) -> Result<(), JsValue> {
-        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&self.gl_buf));
-        let gl_prg = &self.prg;
+        buffer: &mut InstanceBuffer,
+        instances: &[Instance],
+    ) {
+        if instances.is_empty() {
+            return;
+        }
 
-        gl.use_program(Some(gl_prg));
+        self.prg.use_program(gl);
+        gl.uniform2f(
+            self.prg.uniform("dimentions"),
+            self.dimentions.x as f32,
+            self.dimentions.y as f32,
+        );
 
-        let vert_position = gl.get_attrib_location(gl_prg, "vert_position") as u32;
+        let attributes = &self.attributes;
+        self.gl_buf.bind(WebGlRenderingContext::ARRAY_BUFFER);
         gl.vertex_attrib_pointer_with_i32(
-            vert_position,
+            attributes.vert_position,
             2,
             WebGlRenderingContext::FLOAT,
             false,
             0,
             0,
         );
-        gl.enable_vertex_attrib_array(vert_position);
-
-        gl.vertex_attrib1f(
-            gl.get_attrib_location(gl_prg, "rot") as u32,
-            rotation as f32,
-        );
-
-        gl.vertex_attrib2fv_with_f32_array(
-            gl.get_attrib_location(gl_prg, "offset") as u32,
-            offset.coords.cast().as_slice(),
-        );
-
-        gl.vertex_attrib2fv_with_f32_array(
-            gl.get_attrib_location(gl_prg, "dimm") as u32,
-            self.dimentions().cast().as_slice(),
-        );
-
-        gl.vertex_attrib1f(
-            gl.get_attrib_location(gl_prg, "scale") as u32,
-            self.scale as f32,
-        );
-
-        gl.vertex_attrib3fv_with_f32_array(
-            gl.get_attrib_location(gl_prg, "color") as u32,
-            color.cast().as_slice(),
-        );
+        gl.enable_vertex_attrib_array(attributes.vert_position);
 
         let mode = match self.draw_mode {
             DrawMode::LineLoop => WebGlRenderingContext::LINE_LOOP,
@@ -257,8 +139,44 @@ impl Drawable for ObjectDrawable {
             _ => WebGlRenderingContext::LINE_LOOP,
         };
 
-        gl.draw_arrays(mode, 0, self.vertex_count);
+        let Some(ext) = buffer.ext().cloned() else {
+            //constant attributes, one call per instance
+            for instance in instances {
+                gl.vertex_attrib2fv_with_f32_array(
+                    attributes.offset,
+                    instance.offset.coords.cast().as_slice(),
+                );
+                gl.vertex_attrib1f(attributes.rot, instance.rotation as f32);
+                gl.vertex_attrib1f(attributes.scale, instance.scale as f32);
+                gl.vertex_attrib3fv_with_f32_array(
+                    attributes.color,
+                    instance.color.cast().as_slice(),
+                );
+                gl.draw_arrays(mode, 0, self.vertex_count);
+            }
+            return;
+        };
 
-        Ok(())
+        buffer.upload(gl, instances);
+        for (location, size, start) in attributes.per_instance() {
+            gl.vertex_attrib_pointer_with_i32(
+                location,
+                size,
+                WebGlRenderingContext::FLOAT,
+                false,
+                INSTANCE_STRIDE,
+                start * size_of::<f32>() as i32,
+            );
+            gl.enable_vertex_attrib_array(location);
+            ext.vertex_attrib_divisor_angle(location, 1);
+        }
+
+        ext.draw_arrays_instanced_angle(mode, 0, self.vertex_count, instances.len() as i32);
+
+        //the divisor belongs to the location, not the program, the particles use the same ones
+        for (location, _, _) in attributes.per_instance() {
+            ext.vertex_attrib_divisor_angle(location, 0);
+            gl.disable_vertex_attrib_array(location);
+        }
     }
 }
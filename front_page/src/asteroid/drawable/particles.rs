@@ -0,0 +1,165 @@
+use std::mem::size_of;
+use std::rc::Rc;
+
+use crate::asteroid::shader::{self, ShaderError, ShaderProgram};
+
+use super::*;
+use na::Matrix3;
+use trunk_template::sim::{wrapped_copies, DrawMode, Particle, MAX_PARTICLES};
+
+//x, y, r, g, b
+const VERTEX_LEN: usize = 5;
+
+//every particle of the world in a single buffer, rewritten each frame
+//points are stored first and the lines after them, so it takes just two draw calls
+pub struct ParticlesDrawable {
+    prg: Rc<ShaderProgram>,
+    gl_buf: GpuBuffer,
+    //floats the gpu buffer holds
+    capacity: usize,
+    vertices: Vec<f32>,
+    vert_position: u32,
+    vert_color: u32,
+}
+
+impl ParticlesDrawable {
+    pub fn new(resources: &mut GpuResources) -> Result<Self, ShaderError> {
+        let prg = resources.program(
+            "particle",
+            shader::get_vertex_shader_particle(),
+            shader::get_fragment_shader_particle(),
+        )?;
+        let gl = resources.gl();
+
+        //room for every particle as a line (two vertices), it only grows when the copies
+        //past the edges of the map don't fit
+        let capacity = MAX_PARTICLES * 2 * VERTEX_LEN;
+        let gl_buf = GpuBuffer::new(gl)?;
+        gl_buf.bind(WebGlRenderingContext::ARRAY_BUFFER);
+        gl.buffer_data_with_i32(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            (capacity * size_of::<f32>()) as i32,
+            WebGlRenderingContext::DYNAMIC_DRAW,
+        );
+
+        Ok(ParticlesDrawable {
+            vert_position: prg.attribute("vert_position")?,
+            vert_color: prg.attribute("vert_color")?,
+            prg,
+            gl_buf,
+            capacity,
+            vertices: Vec::with_capacity(capacity),
+        })
+    }
+
+    pub fn set_view(&self, gl: &WebGlRenderingContext, projection: Vector2<f64>, pixel_ratio: f64) {
+        self.prg.use_program(gl);
+        gl.uniform2f(
+            self.prg.uniform("projection"),
+            projection.x as f32,
+            projection.y as f32,
+        );
+        gl.uniform1f(self.prg.uniform("pixel_ratio"), pixel_ratio as f32);
+    }
+
+    pub fn set_camera(&self, gl: &WebGlRenderingContext, view: &Matrix3<f64>) {
+        self.prg.use_program(gl);
+        gl.uniform_matrix3fv_with_f32_array(
+            self.prg.uniform("view"),
+            false,
+            view.cast::<f32>().as_slice(),
+        );
+    }
+
+    fn push_vertex(&mut self, pos: Point2<f64>, color: Vector3<f64>) {
+        self.vertices
+            .extend([pos.x, pos.y, color.x, color.y, color.z].map(|x| x as f32));
+    }
+
+    //like the entities, a particle is drawn for every copy of it that can be seen
+    pub fn draw(
+        &mut self,
+        gl: &WebGlRenderingContext,
+        particles: &[Particle],
+        alpha: f64,
+        visible: (Point2<f64>, Point2<f64>),
+        map_dim: Vector2<f64>,
+    ) {
+        self.vertices.clear();
+
+        //the background is white, so fading out means going towards white
+        let color = |particle: &Particle| {
+            let fade = particle.fade();
+            particle.color * fade + Vector3::repeat(1.0 - fade)
+        };
+
+        for particle in particles.iter().filter(|x| x.draw_mode == DrawMode::Points) {
+            let (pos, _) = particle.ends(alpha);
+            for offset in wrapped_copies(pos, 0.0, visible, map_dim) {
+                self.push_vertex(pos + offset, color(particle));
+            }
+        }
+        let points = (self.vertices.len() / VERTEX_LEN) as i32;
+
+        for particle in particles.iter().filter(|x| x.draw_mode == DrawMode::Lines) {
+            let (a, b) = particle.ends(alpha);
+            let center = a + (b - a) / 2.0;
+            for offset in wrapped_copies(center, particle.length / 2.0, visible, map_dim) {
+                self.push_vertex(a + offset, color(particle));
+                self.push_vertex(b + offset, color(particle));
+            }
+        }
+        let lines = (self.vertices.len() / VERTEX_LEN) as i32 - points;
+
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        self.prg.use_program(gl);
+        self.gl_buf.bind(WebGlRenderingContext::ARRAY_BUFFER);
+        unsafe {
+            let vertices = js_sys::Float32Array::view(&self.vertices);
+            if self.vertices.len() > self.capacity {
+                self.capacity = self.vertices.len();
+                gl.buffer_data_with_array_buffer_view(
+                    WebGlRenderingContext::ARRAY_BUFFER,
+                    &vertices,
+                    WebGlRenderingContext::DYNAMIC_DRAW,
+                );
+            } else {
+                gl.buffer_sub_data_with_i32_and_array_buffer_view(
+                    WebGlRenderingContext::ARRAY_BUFFER,
+                    0,
+                    &vertices,
+                );
+            }
+        }
+
+        let stride = (VERTEX_LEN * size_of::<f32>()) as i32;
+        gl.vertex_attrib_pointer_with_i32(
+            self.vert_position,
+            2,
+            WebGlRenderingContext::FLOAT,
+            false,
+            stride,
+            0,
+        );
+        gl.enable_vertex_attrib_array(self.vert_position);
+        gl.vertex_attrib_pointer_with_i32(
+            self.vert_color,
+            3,
+            WebGlRenderingContext::FLOAT,
+            false,
+            stride,
+            2 * size_of::<f32>() as i32,
+        );
+        gl.enable_vertex_attrib_array(self.vert_color);
+
+        gl.draw_arrays(WebGlRenderingContext::POINTS, 0, points);
+        gl.draw_arrays(WebGlRenderingContext::LINES, points, lines);
+
+        //the other programs may use constant attributes at these locations
+        gl.disable_vertex_attrib_array(self.vert_position);
+        gl.disable_vertex_attrib_array(self.vert_color);
+    }
+}
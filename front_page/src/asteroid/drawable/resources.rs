@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use crate::asteroid::shader::{ShaderError, ShaderProgram};
+
+use super::*;
+
+//a buffer on the gpu, deleted when this is dropped
+#[derive(Debug)]
+pub struct GpuBuffer {
+    gl: WebGlRenderingContext,
+    buf: WebGlBuffer,
+}
+
+impl GpuBuffer {
+    pub fn new(gl: &WebGlRenderingContext) -> Result<Self, ShaderError> {
+        let buf = gl.create_buffer().ok_or(ShaderError::Buffer)?;
+        Ok(GpuBuffer {
+            gl: gl.clone(),
+            buf,
+        })
+    }
+
+    pub fn bind(&self, target: u32) {
+        self.gl.bind_buffer(target, Some(&self.buf));
+    }
+}
+
+impl Drop for GpuBuffer {
+    fn drop(&mut self) {
+        self.gl.delete_buffer(Some(&self.buf));
+    }
+}
+
+//programs and static buffers of one webgl context, shared by name between the drawables
+//only weak references are kept, whatever no drawable holds anymore is freed and built again
+//the next time it is asked for
+#[derive(Debug)]
+pub struct GpuResources {
+    gl: WebGlRenderingContext,
+    programs: HashMap<String, Weak<ShaderProgram>>,
+    buffers: HashMap<String, Weak<GpuBuffer>>,
+}
+
+impl GpuResources {
+    pub fn new(gl: &WebGlRenderingContext) -> Self {
+        GpuResources {
+            gl: gl.clone(),
+            programs: HashMap::new(),
+            buffers: HashMap::new(),
+        }
+    }
+
+    pub fn gl(&self) -> &WebGlRenderingContext {
+        &self.gl
+    }
+
+    pub fn program(
+        &mut self,
+        name: &str,
+        vertex_source: &str,
+        fragment_source: &str,
+    ) -> Result<Rc<ShaderProgram>, ShaderError> {
+        if let Some(prg) = self.programs.get(name).and_then(Weak::upgrade) {
+            return Ok(prg);
+        }
+
+        let prg = Rc::new(ShaderProgram::new(
+            &self.gl,
+            vertex_source,
+            fragment_source,
+        )?);
+        self.programs.retain(|_, x| x.strong_count() > 0);
+        self.programs.insert(name.to_string(), Rc::downgrade(&prg));
+        Ok(prg)
+    }
+
+    //a STATIC_DRAW array buffer, `data` is only called when it has to be uploaded
+    pub fn buffer(
+        &mut self,
+        name: &str,
+        data: impl FnOnce() -> Vec<f32>,
+    ) -> Result<Rc<GpuBuffer>, ShaderError> {
+        if let Some(buf) = self.buffers.get(name).and_then(Weak::upgrade) {
+            return Ok(buf);
+        }
+
+        let buf = Rc::new(GpuBuffer::new(&self.gl)?);
+        buf.bind(WebGlRenderingContext::ARRAY_BUFFER);
+        let data = data();
+        unsafe {
+            let data = js_sys::Float32Array::view(&data);
+            self.gl.buffer_data_with_array_buffer_view(
+                WebGlRenderingContext::ARRAY_BUFFER,
+                &data,
+                WebGlRenderingContext::STATIC_DRAW,
+            );
+        }
+
+        self.buffers.retain(|_, x| x.strong_count() > 0);
+        self.buffers.insert(name.to_string(), Rc::downgrade(&buf));
+        Ok(buf)
+    }
+}
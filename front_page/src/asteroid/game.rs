@@ -1,15 +1,23 @@
 use std::mem::size_of;
 
-use nalgebra::{Point2, Rotation2, Vector2};
-use rand::{rngs::ThreadRng, Rng};
-use wasm_bindgen::{JsCast, JsValue};
-use web_sys::{HtmlCanvasElement, WebGlBuffer, WebGlProgram, WebGlRenderingContext};
+use std::collections::HashMap;
 
-use super::{data, shader, EntityDrawable};
+use nalgebra::{Point2, Vector2, Vector3};
+use trunk_template::sim::{
+    wrapped_copies, ControlScheme, Controls, Entity, GameState, Model, Object, PhysicalInput,
+    PowerUpKind, Replay, TouchInput, World, SHIELD_COLOR,
+};
+use wasm_bindgen::JsCast;
+use web_sys::{window, HtmlCanvasElement, WebGlRenderingContext};
+
+use super::camera::Camera;
+use super::shader::{self, ShaderError, ShaderProgram};
+use super::touch::{TouchControls, STICK_RADIUS};
+use super::{GpuBuffer, GpuResources, Instance, InstanceBuffer, ObjectDrawable, ParticlesDrawable};
 
 pub struct TesteDraw {
-    prg: WebGlProgram,
-    gl_buf: WebGlBuffer,
+    prg: ShaderProgram,
+    gl_buf: GpuBuffer,
     count: i32,
     mode: u32,
     psize: f32,
@@ -18,27 +26,20 @@ pub struct TesteDraw {
 }
 
 impl TesteDraw {
-    pub fn new(gl: &WebGlRenderingContext, vertices: &[f32], mode: u32, psize: f32) -> Self {
-        let prg = gl.create_program().unwrap();
-
-        let vertex_shader = gl
-            .create_shader(WebGlRenderingContext::VERTEX_SHADER)
-            .unwrap();
-        gl.shader_source(&vertex_shader, shader::get_vertex_shader_teste());
-        gl.compile_shader(&vertex_shader);
-
-        let fragment_shader = gl
-            .create_shader(WebGlRenderingContext::FRAGMENT_SHADER)
-            .unwrap();
-        gl.shader_source(&fragment_shader, shader::get_fragment_shader_teste());
-        gl.compile_shader(&fragment_shader);
-
-        gl.attach_shader(&prg, &vertex_shader);
-        gl.attach_shader(&prg, &fragment_shader);
-        gl.link_program(&prg);
+    pub fn new(
+        gl: &WebGlRenderingContext,
+        vertices: &[f32],
+        mode: u32,
+        psize: f32,
+    ) -> Result<Self, ShaderError> {
+        let prg = ShaderProgram::new(
+            gl,
+            shader::get_vertex_shader_teste(),
+            shader::get_fragment_shader_teste(),
+        )?;
 
-        let gl_buf = gl.create_buffer().unwrap();
-        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&gl_buf));
+        let gl_buf = GpuBuffer::new(gl)?;
+        gl_buf.bind(WebGlRenderingContext::ARRAY_BUFFER);
         unsafe {
             let vert_array = js_sys::Float32Array::view(vertices);
             gl.buffer_data_with_array_buffer_view(
@@ -48,21 +49,21 @@ impl TesteDraw {
             );
         }
 
-        Self {
+        Ok(Self {
             mode,
             psize,
-            vertex_pos: gl.get_attrib_location(&prg, "vert_position") as u32,
-            point_size: gl.get_attrib_location(&prg, "pointSize") as u32,
+            vertex_pos: prg.attribute("vert_position")?,
+            point_size: prg.attribute("pointSize")?,
             prg,
             gl_buf,
             count: vertices.len() as i32 / 2,
-        }
+        })
     }
 
     pub fn draw(&self, gl: &WebGlRenderingContext) {
-        gl.use_program(Some(&self.prg));
+        self.prg.use_program(gl);
 
-        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&self.gl_buf));
+        self.gl_buf.bind(WebGlRenderingContext::ARRAY_BUFFER);
 
         gl.vertex_attrib_pointer_with_i32(
             self.vertex_pos,
@@ -80,224 +81,257 @@ impl TesteDraw {
     }
 }
 
-#[derive(Default, Debug)]
-pub struct MouseInput {
-    pub pos: Point2<f64>,
-    pub left: bool,
-    pub right: bool,
+//part of the world shown on a canvas of the given size, the whole map always fits and
+//the longer side of the canvas shows more of the world instead of stretching it
+pub fn view_dim(canvas_dim: Vector2<f64>, map_dim: Vector2<f64>) -> Vector2<f64> {
+    let units_per_pixel = map_dim.component_div(&canvas_dim).max();
+    canvas_dim * units_per_pixel
 }
 
-#[derive(Debug)]
-pub enum ButtonState {
-    None,
-    Pressed,
-    Hold,
-    Released,
-}
+//everything `Game` keeps on the gpu, see `load_drawables`
+type GpuDrawables = (
+    HashMap<Model, ObjectDrawable>,
+    ParticlesDrawable,
+    InstanceBuffer,
+);
 
-#[derive(Debug)]
-pub struct KeyBoardInput {
-    pub space: ButtonState,
+//draws a `World` with webgl and translates browser input into it
+pub struct Game {
+    pub world: World,
+    gl: WebGlRenderingContext,
+    //programs and model buffers of `gl`, shared by the drawables
+    resources: GpuResources,
+    drawables: HashMap<Model, ObjectDrawable>,
+    particles: ParticlesDrawable,
+    instances: InstanceBuffer,
+    //copies of each model to draw this frame, kept to reuse the memory
+    batches: HashMap<Model, Vec<Instance>>,
+    canvas: HtmlCanvasElement,
+    //size of the canvas on the page in css pixels, the pointer positions are in these
+    pub canvas_dim: Vector2<f64>,
+    //canvas pixels per css pixel
+    pixel_ratio: f64,
+    //size of the part of the world shown, see `view_dim`
+    view_dim: Vector2<f64>,
+    //simulation steps run per frame worth of time, used to fast forward replays
+    pub speed: u32,
+    //chosen by the player, a replay brings its own while it plays
+    scheme: ControlScheme,
+    //keys and buttons to actions, see `Bindings`
+    pub controls: Controls,
+    pub touch: TouchControls,
+    //last mouse position on the canvas, the pointer moves with the camera even if the mouse doesn't
+    mouse: Option<Point2<f64>>,
+    pub camera: Camera,
+    //the browser dropped the webgl context, see `lose_context`
+    context_lost: bool,
 }
 
-impl Default for KeyBoardInput {
-    fn default() -> Self {
-        Self {
-            space: ButtonState::None,
-        }
-    }
-}
+impl Game {
+    pub fn new(canvas: HtmlCanvasElement, seed: u64) -> Result<Self, ShaderError> {
+        let gl: WebGlRenderingContext = canvas
+            .get_context("webgl")
+            .unwrap()
+            .unwrap()
+            .dyn_into::<web_sys::WebGlRenderingContext>()
+            .unwrap();
 
-#[derive(Default, Debug)]
-pub struct UserInput {
-    pub mouse: MouseInput,
-    pub keyboard: KeyBoardInput,
-}
+        let mut resources = GpuResources::new(&gl);
+        let (drawables, particles, instances) = Game::load_drawables(&mut resources)?;
 
-#[derive(Clone, Debug)]
-enum GameEntity {
-    Asteroid {
-        entity: EntityDrawable,
-        size: u32,
-        hit: bool,
-    },
-    Bullet {
-        entity: EntityDrawable,
-        hit: bool,
-    },
-}
+        let mut game = Self {
+            world: World::new(seed),
+            gl,
+            resources,
+            drawables,
+            particles,
+            instances,
+            batches: HashMap::new(),
+            canvas,
+            canvas_dim: Vector2::zeros(),
+            pixel_ratio: 1.0,
+            view_dim: Vector2::zeros(),
+            speed: 1,
+            scheme: ControlScheme::default(),
+            controls: Controls::default(),
+            touch: TouchControls::default(),
+            mouse: None,
+            camera: Camera::default(),
+            context_lost: false,
+        };
+        game.fit_canvas();
+        Ok(game)
+    }
 
-impl GameEntity {
-    fn set_hit(&mut self, hit: bool) {
-        match self {
-            GameEntity::Asteroid { hit: h, .. } => *h = hit,
-            GameEntity::Bullet { hit: h, .. } => *h = hit,
-            _ => {}
-        }
+    //builds every program and buffer from the shaders and the json of the models
+    fn load_drawables(resources: &mut GpuResources) -> Result<GpuDrawables, ShaderError> {
+        let drawables = Model::ALL
+            .iter()
+            .map(|model| {
+                let drawable = ObjectDrawable::load_gl(&Object::load(*model), resources)?;
+                Ok((*model, drawable))
+            })
+            .collect::<Result<_, ShaderError>>()?;
+
+        Ok((
+            drawables,
+            ParticlesDrawable::new(resources)?,
+            InstanceBuffer::new(resources.gl())?,
+        ))
     }
-    fn is_hit(&self) -> bool {
-        match self {
-            GameEntity::Asteroid { hit, .. } => *hit,
-            GameEntity::Bullet { hit, .. } => *hit,
-            _ => false,
-        }
+
+    //every program and buffer is gone with the context, nothing runs until it is restored
+    pub fn lose_context(&mut self) {
+        self.context_lost = true;
     }
-    fn get_entity(&self) -> &EntityDrawable {
-        match self {
-            GameEntity::Asteroid { entity, .. } => entity,
-            GameEntity::Bullet { entity, .. } => entity,
-        }
+
+    //the restored context starts empty, the world was kept so the game goes on from where it was
+    pub fn restore_context(&mut self) -> Result<(), ShaderError> {
+        //the old handles belong to the lost context, nothing can be shared with them
+        self.resources = GpuResources::new(&self.gl);
+        let (drawables, particles, instances) = Game::load_drawables(&mut self.resources)?;
+        self.drawables = drawables;
+        self.particles = particles;
+        self.instances = instances;
+        self.context_lost = false;
+
+        //the viewport and the view uniforms are set again on the next frame
+        self.canvas_dim = Vector2::zeros();
+        Ok(())
     }
 
-    fn get_entity_mut(&mut self) -> &mut EntityDrawable {
-        match self {
-            GameEntity::Asteroid { entity, .. } => entity,
-            GameEntity::Bullet { entity, .. } => entity,
+    //keeps the drawing buffer as big as the canvas on the page, checked every frame so it
+    //follows window resizes, layout changes and devicePixelRatio changes (zoom, another monitor)
+    fn fit_canvas(&mut self) {
+        let pixel_ratio = window().map_or(1.0, |x| x.device_pixel_ratio());
+        let canvas_dim = Vector2::new(
+            self.canvas.client_width() as f64,
+            self.canvas.client_height() as f64,
+        );
+        let changed = canvas_dim != self.canvas_dim || pixel_ratio != self.pixel_ratio;
+        //hidden, there is nothing to draw on
+        if !changed || canvas_dim.min() <= 0.0 {
+            return;
         }
-    }
-}
 
-pub struct Game {
-    player: EntityDrawable,
-    entities: Vec<GameEntity>,
-    gl: WebGlRenderingContext,
-    pub canvas_dim: Vector2<f64>,
-    pub map_dim: Vector2<f64>,
-    pub input: UserInput,
-    rng: rand::rngs::ThreadRng,
-    last_shoot: f64,
-    level: u32,
-    kill_count: u32,
-}
+        let size = (canvas_dim * pixel_ratio).map(|x| x.round());
+        self.canvas.set_width(size.x as u32);
+        self.canvas.set_height(size.y as u32);
+        self.gl.viewport(0, 0, size.x as i32, size.y as i32);
 
-impl EntityDrawable {
-    fn process_player_acc(&mut self, mouse: Point2<f64>) {
-        //get center of player
-        let player_pos = self.get_pos_center();
+        self.canvas_dim = canvas_dim;
+        self.pixel_ratio = pixel_ratio;
+        self.view_dim = view_dim(canvas_dim, self.world.map_dim);
 
-        //calcule acceleration vector based on mouse position
-        let dir_vector = mouse - player_pos;
-        let player_acc: Vector2<f64> = dir_vector.normalize() * 0.001;
-        self.acc = player_acc;
+        let projection = self.view_dim.map(|x| 2.0 / x);
+        for drawable in self.drawables.values() {
+            drawable.set_view(&self.gl, projection, pixel_ratio);
+        }
+        self.particles.set_view(&self.gl, projection, pixel_ratio);
     }
 
-    fn process_player_rot(&mut self, mouse: Point2<f64>) {
-        //get center of player
-        let player_pos = self.pos.coords + self.object.dimentions() / 2.0;
-        let dir_vector = mouse - player_pos;
-        //calcule rotation based on mouse position
-        self.rotation = {
-            let rotation = (dir_vector.y / dir_vector.coords.norm()).acos();
-            if dir_vector.x < 0.0 {
-                -rotation
-            } else {
-                rotation
-            }
-        };
+    pub fn play_replay(&mut self, replay: Replay) {
+        self.world = World::from_replay(replay);
+        self.speed = 1;
     }
 
-    fn process_teleport(&mut self) {
-        if self.pos.x > 1000.0 {
-            self.pos.x = -1000.0;
-        } else if self.pos.x < -1000.0 {
-            self.pos.x = 1000.0;
-        }
-        if self.pos.y > 1000.0 {
-            self.pos.y = -1000.0;
-        } else if self.pos.y < -1000.0 {
-            self.pos.y = 1000.0;
-        }
+    //back to a live game on the title screen
+    pub fn stop_replay(&mut self, seed: u64) {
+        self.world = World::new(seed);
+        self.world.input.scheme = self.scheme;
+        self.speed = 1;
     }
 
-    fn process_redraw(&self, gl: &WebGlRenderingContext) -> Result<(), JsValue> {
-        let dimm = self.object.dimentions();
-        let mut pos = self.pos;
-        if self.pos.x + dimm.x > 1000.0 {
-            pos.x -= 2000.0;
-            self.draw_position(gl, pos)?
-        }
-        if self.pos.y + dimm.y > 1000.0 {
-            pos.y -= 2000.0;
-            self.draw_position(gl, pos)?
-        }
-        Ok(())
+    //position on the canvas (in css pixels) to world coordinates
+    pub fn canvas_to_world(&self, canvas_pos: Point2<f64>) -> Point2<f64> {
+        let offset_center: Vector2<f64> = Vector2::new(0.5, 0.5);
+
+        let mut pos: Vector2<f64> =
+            canvas_pos.coords.component_div(&self.canvas_dim) - offset_center;
+
+        pos.y *= -1.0;
+        let view_pos: Point2<f64> = pos.component_mul(&self.view_dim).into();
+        self.camera.inverse_view().transform_point(&view_pos)
     }
 
-    fn shoud_delete(&self) -> bool {
-        if self.pos.x > 1000.0 {
-            return true;
-        } else if self.pos.x < -1000.0 {
-            return true;
+    pub fn set_pointer(&mut self, canvas_pos: Point2<f64>) {
+        self.mouse = Some(canvas_pos);
+        self.world.input.pointer = self.canvas_to_world(canvas_pos);
+        //the mouse took over, the ship stops following the touch stick
+        if self.touch.aim().is_some() {
+            self.touch.clear();
+            self.touch.apply(&mut self.controls);
         }
-        if self.pos.y > 1000.0 {
-            return true;
-        } else if self.pos.y < -1000.0 {
-            return true;
+    }
+
+    pub fn touch_start(&mut self, id: i32, canvas_pos: Point2<f64>) {
+        //outside of a game a tap works like Enter
+        if !matches!(self.world.state(), GameState::Playing | GameState::Dying) {
+            let tap = PhysicalInput::Touch(TouchInput::Screen);
+            self.controls.press(tap.clone());
+            self.controls.release(&tap);
+            return;
         }
-        false
+        self.mouse = None;
+        self.touch.start(id, canvas_pos, self.canvas_dim.x);
+        self.touch.apply(&mut self.controls);
     }
-}
 
-impl Game {
-    pub fn new(canvas: HtmlCanvasElement) -> Self {
-        let rng = rand::thread_rng();
+    pub fn touch_move(&mut self, id: i32, canvas_pos: Point2<f64>) {
+        self.touch.move_to(id, canvas_pos);
+        self.touch.apply(&mut self.controls);
+    }
 
-        let gl: WebGlRenderingContext = canvas
-            .get_context("webgl")
-            .unwrap()
-            .unwrap()
-            .dyn_into::<web_sys::WebGlRenderingContext>()
-            .unwrap();
+    pub fn touch_end(&mut self, id: i32) {
+        self.touch.end(id);
+        self.touch.apply(&mut self.controls);
+    }
 
-        let mut player = EntityDrawable::load_gl(&gl, data::get_ship());
-
-        player.speed = Vector2::new(0.0, 0.0);
-        player.pos = Point2::new(0.0, 0.0);
-        player.max_speed_sqr = 0.3;
-        player.rotation = 0f64.to_radians();
-        player.delete_on_out_of_bounds = false;
-
-        // let mut person2 = EntityDrawable::load_gl(&gl, data::get_asteroid());
-        // person2.object.scale = 6.0;
-        // person2.speed = Vector2::new(0.0, 0.0);
-        // person2.pos = Point2::new(500.0, 500.0) - person2.object.dimentions() / 2.0;
-        // // person2.pos = Game::random_point(&mut rng, Vector2::new(1000.0, 1000.0));
-        // person2.max_speed_sqr = 0.3;
-        // person2.rotation = 0f64.to_radians();
-        // person2.delete_on_out_of_bounds = false;
-        // entities.push(person2);
-
-        // gl.viewport(0, 0, canvas.width() as i32, canvas.height() as i32);
-
-        Self {
-            player,
-            entities: vec![],
-            gl,
-            canvas_dim: Vector2::new(canvas.width() as f64, canvas.height() as f64),
-            map_dim: Vector2::new(2000.0, 2000.0),
-            input: Default::default(),
-            rng,
-            last_shoot: 0.0,
-            level: 1,
-            kill_count: 0,
+    //the touch stick steers like the mouse would, pointing away from the ship
+    fn update_pointer(&mut self) {
+        if let Some(mouse) = self.mouse {
+            self.world.input.pointer = self.canvas_to_world(mouse);
         }
+        let (Some(aim), Some(player)) = (self.touch.aim(), self.world.player()) else {
+            return;
+        };
+        let center = Point2::from(self.canvas_dim / 2.0);
+        let dir = self.canvas_to_world(center + aim * STICK_RADIUS) - self.canvas_to_world(center);
+        self.world.input.pointer = player.get_pos_center() + dir;
     }
 
-    pub fn update_input(&mut self) {
-        let offset_center: Vector2<f64> = Vector2::new(0.5, 0.5);
+    //shakes with the explosions and follows the ship where it is drawn
+    fn update_camera(&mut self, delta: f64) {
+        let alpha = self.world.alpha();
+        let target = self.world.player().map(|player| {
+            let (pos, _) = player.interpolated(alpha);
+            pos + player.object.dimentions() / 2.0
+        });
+        self.camera.update(delta, target, self.world.map_dim);
+
+        let view = self.camera.view();
+        for drawable in self.drawables.values() {
+            drawable.set_camera(&self.gl, &view);
+        }
+        self.particles.set_camera(&self.gl, &view);
+    }
 
-        let mut pos: Vector2<f64> =
-            self.input.mouse.pos.coords.component_div(&self.canvas_dim) - offset_center;
+    pub fn scheme(&self) -> ControlScheme {
+        self.scheme
+    }
 
-        pos.y *= -1.0;
-        self.input.mouse.pos = pos.component_mul(&self.map_dim).into();
+    pub fn set_scheme(&mut self, scheme: ControlScheme) {
+        self.scheme = scheme;
+        self.world.input.scheme = scheme;
     }
 
     #[allow(dead_code)]
     fn draw_debug_point(gl: &WebGlRenderingContext, point: &Point2<f64>) {
         let a: &[f32] = &[point.x as f32, point.y as f32];
-        let t = TesteDraw::new(gl, a, WebGlRenderingContext::POINTS, 6.0);
-        t.draw(gl);
+        match TesteDraw::new(gl, a, WebGlRenderingContext::POINTS, 6.0) {
+            Ok(t) => t.draw(gl),
+            Err(err) => log::error!("{}", err),
+        }
     }
 
     #[allow(dead_code)]
@@ -308,33 +342,9 @@ impl Game {
             pos.x as f32,
             pos.y as f32,
         ];
-        let t = TesteDraw::new(gl, a, WebGlRenderingContext::LINES, 0.0);
-        t.draw(gl);
-    }
-
-    fn spawn_bullet(
-        player: &EntityDrawable,
-        input: &UserInput,
-        gl: &WebGlRenderingContext,
-    ) -> GameEntity {
-        let nav_center: Vector2<f64> = player.get_pos_center().coords;
-        let rot = Rotation2::new(-player.rotation);
-        let up = rot * (Vector2::y() * player.object.dimentions().y / 2.0);
-
-        let coors = nav_center + up;
-
-        let player_dim = player.object.dimentions();
-        let player_pos = player.pos + player_dim / 2.0;
-        let dir_vector = (input.mouse.pos.coords - player_pos.coords).normalize();
-
-        let mut bullet = EntityDrawable::load_gl(gl, data::get_bullet());
-        bullet.delete_on_out_of_bounds = true;
-        // bullet.object.scale = 5.0;
-        bullet.pos = (coors - bullet.object.dimentions() / 2.0).into();
-        bullet.speed = dir_vector * 1.0 + player.speed;
-        GameEntity::Bullet {
-            entity: bullet,
-            hit: false,
+        match TesteDraw::new(gl, a, WebGlRenderingContext::LINES, 0.0) {
+            Ok(t) => t.draw(gl),
+            Err(err) => log::error!("{}", err),
         }
     }
 
@@ -384,198 +394,88 @@ impl Game {
     //     // offset += 1.0;
     // }
 
-    fn random_point(rng: &mut ThreadRng, map_dim: Vector2<f64>) -> Point2<f64> {
-        Point2::new(rng.gen_range(0.0..map_dim.x), rng.gen_range(0.0..map_dim.y))
+    //corners of the part of the world on the canvas, bigger than the map when zoomed out
+    fn visible_area(&self) -> (Point2<f64>, Point2<f64>) {
+        let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].map(|(x, y)| {
+            self.canvas_to_world(
+                Point2::new(x, y)
+                    .coords
+                    .component_mul(&self.canvas_dim)
+                    .into(),
+            )
+        });
+        let min = corners.iter().fold(corners[0], |a, b| a.inf(b));
+        let max = corners.iter().fold(corners[0], |a, b| a.sup(b));
+        (min, max)
     }
 
-    fn spawn_asteroid(&mut self) -> GameEntity {
-        let mut asteroid = EntityDrawable::load_gl(&self.gl, data::get_asteroid());
-
-        let max_speed = 0.6;
+    //the map wraps around, so the entity is drawn once for every copy of it that can be seen
+    //an entity on an edge or a corner shows up on the other sides as well
+    fn batch_entity(
+        &self,
+        batches: &mut HashMap<Model, Vec<Instance>>,
+        entity: &Entity,
+        alpha: f64,
+        visible: (Point2<f64>, Point2<f64>),
+    ) {
+        let (pos, rotation) = entity.interpolated(alpha);
+        //the model turns around its center, so it never leaves this circle
+        let dimm = entity.object.dimentions();
+        let (center, radius) = (pos + dimm / 2.0, dimm.norm() / 2.0);
+
+        let batch = batches.entry(entity.object.model).or_default();
+        for offset in wrapped_copies(center, radius, visible, self.world.map_dim) {
+            batch.push(Instance {
+                offset: pos + offset,
+                rotation,
+                scale: entity.object.scale,
+                color: entity.color,
+            });
+        }
+    }
 
-        let pos = Game::random_point(&mut self.rng, self.map_dim);
-        asteroid.speed = Vector2::new(
-            self.rng.gen_range(0.0..=max_speed),
-            self.rng.gen_range(0.0..=max_speed),
-        );
-        asteroid.pos = pos;
-        asteroid.object.scale = 2.0 * 3.0;
-        asteroid.rotation = (self.rng.gen_range(0.0..=360.0) as f64).to_radians();
-        asteroid.delete_on_out_of_bounds = false;
-
-        GameEntity::Asteroid {
-            entity: asteroid,
-            size: 2,
-            hit: false,
+    //bubble around the ship, a bit bigger than it
+    fn batch_shield(
+        &self,
+        batches: &mut HashMap<Model, Vec<Instance>>,
+        player: &Entity,
+        alpha: f64,
+        color: Vector3<f64>,
+        visible: (Point2<f64>, Point2<f64>),
+    ) {
+        let shield = &self.drawables[&Model::Shield];
+        let scale = player.object.dimentions().max() * 1.3 / shield.dimentions.max();
+
+        let (pos, _) = player.interpolated(alpha);
+        let center = pos + player.object.dimentions() / 2.0;
+        let radius = shield.dimentions.max() * scale / 2.0;
+        let pos = center - shield.dimentions * scale / 2.0;
+
+        let batch = batches.entry(Model::Shield).or_default();
+        for offset in wrapped_copies(center, radius, visible, self.world.map_dim) {
+            batch.push(Instance {
+                offset: pos + offset,
+                rotation: 0.0,
+                scale,
+                color,
+            });
         }
     }
 
-    pub fn game_loop(&mut self, time: f64, delta: f64) {
+    fn draw(&mut self) {
         self.gl.clear_color(1.0, 1.0, 1.0, 1.0);
         self.gl.clear(
             WebGlRenderingContext::COLOR_BUFFER_BIT | WebGlRenderingContext::DEPTH_BUFFER_BIT,
         );
 
-        if self
-            .entities
-            .iter()
-            .filter(|x| match x {
-                GameEntity::Asteroid { .. } => true,
-                _ => false,
-            })
-            .count()
-            == 0
-        {
-            let qtd = 2 + self.level;
-            //let qtd = 1;
-            for _ in 0..qtd {
-                let entity = self.spawn_asteroid();
-                self.entities.push(entity);
-            }
-            self.level += 1;
-        }
-
-        // self.draw_vector();
-
-        // let data = [-1.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 1.0];
-        // let teste = TesteDraw::new(&self.gl, &data, WebGlRenderingContext::LINES, 0f32);
-        // teste.draw(&self.gl);
-
-        if self.input.mouse.left {
-            self.player.process_player_acc(self.input.mouse.pos);
-        } else if self.player.speed.magnitude() > 0.001 {
-            let arrasto: Vector2<f64> = self.player.speed.normalize() * -0.00007;
-            self.player.acc = arrasto;
-        } else {
-            self.player.acc = Vector2::default();
-            self.player.speed = Vector2::default();
-        }
-
-        if self.input.mouse.left || self.input.mouse.right {
-            self.player.process_player_rot(self.input.mouse.pos);
-        }
-
-        if self.input.mouse.right {
-            // if (time - self.last_shoot) > 200.0 {
-            let bullet = Game::spawn_bullet(&self.player, &self.input, &self.gl);
-            self.entities.push(bullet);
-            self.last_shoot = time;
-            // }
-        }
-
-        // if let ButtonState::Pressed = self.input.keyboard.space {
-        //     let entity = self.spawn_asteroid();
-        //     self.entities.push(entity);
-        // }
-
-        self.player.update_physics(delta);
-        //physics loop
-        for entity in self.entities.iter_mut().map(|x| x.get_entity_mut()) {
-            entity.update_physics(delta);
-        }
-
-        let index_bullet: Vec<usize> = self
-            .entities
-            .iter()
-            .enumerate()
-            .filter(|(y, x)| match x {
-                GameEntity::Bullet { .. } => true,
-                _ => false,
-            })
-            .map(|x| x.0)
-            .collect();
-
-        let index_asteroid: Vec<usize> = self
-            .entities
-            .iter()
-            .enumerate()
-            .filter(|(y, x)| match x {
-                GameEntity::Asteroid { .. } => true,
-                _ => false,
-            })
-            .map(|x| x.0)
-            .collect();
-
-        let mut new_asteroid: Vec<GameEntity> = vec![];
-        for i in index_asteroid {
-            for j in index_bullet.clone() {
-                if self.entities[i].is_hit() || self.entities[j].is_hit() {
-                    continue;
-                }
-
-                if self.entities[i]
-                    .get_entity()
-                    .hit2(&self.entities[j].get_entity())
-                {
-                    self.kill_count += 1;
-                    log::info!("kill count: {}", self.kill_count);
-
-                    self.entities.get_mut(i).unwrap().set_hit(true);
-                    self.entities.get_mut(j).unwrap().set_hit(true);
-
-                    if let GameEntity::Asteroid { size, .. } = self.entities[i] {
-                        if size <= 1 {
-                            continue;
-                        }
-                        let size = size - 1;
-                        let mut asteroid = self.entities[i].get_entity().clone();
-                        asteroid.object.scale = 3.0 * (size as f64);
-                        asteroid.speed = Vector2::new(
-                            self.rng.gen_range(0.0..=0.6),
-                            self.rng.gen_range(0.0..=0.6),
-                        );
-                        asteroid.rotation = (self.rng.gen_range(0.0..=360.0) as f64).to_radians();
-
-                        new_asteroid.push(GameEntity::Asteroid {
-                            entity: asteroid,
-                            size: size,
-                            hit: false,
-                        });
-
-                        let mut asteroid = self.entities[i].get_entity().clone();
-                        asteroid.object.scale = 3.0 * (size as f64);
-                        asteroid.speed = Vector2::new(
-                            self.rng.gen_range(0.0..=0.6),
-                            self.rng.gen_range(0.0..=0.6),
-                        );
-                        asteroid.rotation = (self.rng.gen_range(0.0..=360.0) as f64).to_radians();
-
-                        new_asteroid.push(GameEntity::Asteroid {
-                            entity: asteroid,
-                            size: size,
-                            hit: false,
-                        });
-                    }
-
-                    continue;
-                }
-            }
-
-            if self.player.hit2(&self.entities[i].get_entity()) {
-                self.kill_count += 0;
-                log::info!("player has died");
-            }
-        }
-
-        new_asteroid.extend(self.entities.clone());
-        self.entities = new_asteroid
-            .iter()
-            .filter(|x| {
-                !(x.get_entity().delete_on_out_of_bounds && x.get_entity().shoud_delete()
-                    || x.is_hit())
-            })
-            .cloned()
-            .collect();
-
-        for entity in self.entities.iter_mut() {
-            entity.get_entity_mut().process_teleport();
-        }
+        let alpha = self.world.alpha();
+        let visible = self.visible_area();
 
         //uncoment for debug colisions triagles
-        // for entity in self.entities.iter() {
+        // for entity in self.world.entities() {
         //     for triagle in entity.object.lst_hit_box.iter() {
         //         let triagle: Matrix2x3<f64> =
-        //             EntityDrawable::transform_triagle(entity, *triagle) / 1000.0;
+        //             Entity::transform_triagle(entity, *triagle) / 1000.0;
 
         //         for i in 0..3 {
         //             let next = (i + 1) % 3;
@@ -588,17 +488,62 @@ impl Game {
         //     }
         // }
 
-        // //Draw loop
-        for entity in self.entities.iter().map(|x| x.get_entity()) {
-            entity.draw(&self.gl).unwrap();
-            entity.process_redraw(&self.gl).unwrap();
+        let mut batches = std::mem::take(&mut self.batches);
+        batches.values_mut().for_each(Vec::clear);
+
+        for entity in self.world.entities() {
+            self.batch_entity(&mut batches, entity, alpha, visible);
+        }
+
+        if let Some(player) = self.world.player().filter(|_| self.world.player_visible()) {
+            self.batch_entity(&mut batches, player, alpha, visible);
+
+            if self.world.shield_up() {
+                self.batch_shield(&mut batches, player, alpha, SHIELD_COLOR, visible);
+            } else if self.world.has_effect(PowerUpKind::Shield) {
+                let color = PowerUpKind::Shield.color();
+                self.batch_shield(&mut batches, player, alpha, color, visible);
+            }
         }
 
-        self.player.draw(&self.gl).unwrap();
-        self.player.process_redraw(&self.gl).unwrap();
+        //one draw call per model, the shield goes over the ship
+        for model in Model::ALL {
+            if let Some(batch) = batches.get(&model) {
+                self.drawables[&model].draw(&self.gl, &mut self.instances, batch);
+            }
+        }
+        self.batches = batches;
+
+        self.particles.draw(
+            &self.gl,
+            self.world.particles(),
+            alpha,
+            visible,
+            self.world.map_dim,
+        );
 
         // {
         //     self.draw_text(time, delta);
         // }
     }
+
+    //delta is the real time since the last frame
+    pub fn game_loop(&mut self, delta: f64) {
+        //the simulation waits too, drawing is impossible and the player can't see what happens
+        if self.context_lost {
+            return;
+        }
+        self.fit_canvas();
+        self.update_pointer();
+        for _ in 0..self.speed {
+            let controls = &mut self.controls;
+            self.world
+                .advance_with(delta, |input| controls.latch(&mut input.actions));
+            for explosion in self.world.explosions() {
+                self.camera.add_trauma(explosion.strength);
+            }
+        }
+        self.update_camera(delta);
+        self.draw();
+    }
 }
@@ -0,0 +1,35 @@
+use trunk_template::sim::{stick_inputs, PhysicalInput};
+use wasm_bindgen::JsCast;
+use web_sys::{Gamepad, GamepadButton};
+
+//buttons and stick directions held right now on any connected gamepad
+pub fn read_gamepads() -> Vec<PhysicalInput> {
+    let mut inputs = Vec::new();
+    let Some(gamepads) = web_sys::window().and_then(|x| x.navigator().get_gamepads().ok()) else {
+        return inputs;
+    };
+
+    //the empty slots of the list are null
+    for gamepad in gamepads.iter().filter_map(|x| x.dyn_into::<Gamepad>().ok()) {
+        if !gamepad.connected() {
+            continue;
+        }
+
+        for (i, button) in gamepad.buttons().iter().enumerate() {
+            let pressed = button
+                .dyn_into::<GamepadButton>()
+                .map(|x| x.pressed())
+                .unwrap_or(false);
+            if pressed {
+                inputs.push(PhysicalInput::PadButton(i as u8));
+            }
+        }
+
+        //the axes come in pairs, x and y of each stick
+        let axes: Vec<f64> = gamepad.axes().iter().filter_map(|x| x.as_f64()).collect();
+        for (i, pair) in axes.chunks_exact(2).enumerate() {
+            inputs.extend(stick_inputs(2 * i as u8, pair[0], pair[1]));
+        }
+    }
+    inputs
+}
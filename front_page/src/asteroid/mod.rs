@@ -1,49 +1,226 @@
 extern crate nalgebra as na;
 
-mod data;
+mod camera;
 mod drawable;
-mod entity;
 mod game;
+mod gamepad;
 mod shader;
+mod touch;
 
-pub use data::*;
 pub use drawable::*;
-pub use entity::*;
 use wasm_bindgen::{prelude::Closure, JsCast};
 
-use std::{cell::RefCell, rc::Rc};
-
-use crate::asteroid::game::ButtonState;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
 use super::hook::*;
 use game::Game;
+use gamepad::read_gamepads;
 use na::Point2;
+use trunk_template::sim::{
+    Action, Bindings, ControlScheme, Controls, GameState, PhysicalInput, Replay,
+};
 use web_sys::*;
 use yew::prelude::*;
 
+//a última partida fica salva no navegador para poder ser assistida
+const REPLAY_KEY: &str = "asteroid.replay";
+
+fn local_storage() -> Option<Storage> {
+    window()?.local_storage().ok()?
+}
+
+fn load_replay() -> Option<String> {
+    local_storage()?.get_item(REPLAY_KEY).ok()?
+}
+
+//o erro vem como texto para aparecer na tela (o localStorage pode estar cheio ou desativado)
+fn save_replay(json: &str) -> Result<(), String> {
+    let storage = local_storage().ok_or("localStorage is not available")?;
+    storage.set_item(REPLAY_KEY, json).map_err(|err| {
+        log::warn!("could not save the replay: {:?}", err);
+        err.dyn_ref::<js_sys::Error>()
+            .map(|x| String::from(x.message()))
+            .unwrap_or_else(|| "the browser refused to store it".to_string())
+    })
+}
+
+//os controles escolhidos pelo jogador também
+const BINDINGS_KEY: &str = "asteroid.bindings";
+
+fn load_bindings() -> Bindings {
+    let Some(json) = local_storage().and_then(|x| x.get_item(BINDINGS_KEY).ok().flatten()) else {
+        return Bindings::default();
+    };
+    Bindings::from_json(&json).unwrap_or_else(|err| {
+        log::warn!("could not load the bindings: {}", err);
+        Bindings::default()
+    })
+}
+
+fn save_bindings(bindings: &Bindings) {
+    if let Some(storage) = local_storage() {
+        if let Err(err) = storage.set_item(BINDINGS_KEY, &bindings.to_json()) {
+            log::warn!("could not save the bindings: {:?}", err);
+        }
+    }
+}
+
+fn random_seed() -> u64 {
+    (js_sys::Math::random() * u32::MAX as f64) as u64
+}
+
 #[function_component]
 pub fn Asteroid() -> Html {
-    let game: Rc<RefCell<Option<Game>>> = Rc::new(RefCell::new(None));
+    let game: Rc<RefCell<Option<Game>>> = use_mut_ref(|| None);
+    //estado exposto para a página poder desenhar os overlays
+    let state = use_state_eq(|| GameState::Title);
+    let lives = use_state_eq(|| 0u32);
+    let score = use_state_eq(|| 0u32);
+    let multiplier = use_state_eq(|| 1u32);
+    //power ups ativos e os segundos que ainda restam
+    let effects = use_state_eq(Vec::<(&'static str, u32)>::new);
+    //carga do escudo em % e se o hiperespaço está disponível
+    let energy = use_state_eq(|| 100u32);
+    let hyperspace_ready = use_state_eq(|| true);
+    //(passo atual, total de passos) enquanto um replay está tocando
+    let playback = use_state_eq(|| None::<(usize, usize)>);
+    let has_replay = use_state_eq(|| load_replay().is_some());
+    //por que o último replay não pôde ser salvo ou carregado
+    let replay_error = use_state_eq(|| None::<String>);
+    let scheme = use_state_eq(ControlScheme::default);
+    //onde o dedo do joystick encostou e onde ele está, para desenhar o joystick
+    let touch_stick = use_state_eq(|| None::<(Point2<f64>, Point2<f64>)>);
+    //o botão de tiro só aparece depois do primeiro toque
+    let touch_used = use_state_eq(|| false);
+    //câmera seguindo a nave e o zoom em %
+    let follow = use_state_eq(|| false);
+    let zoom = use_state_eq(|| 100u32);
+    //cópia das teclas do jogo para desenhar o painel de controles
+    let bindings = use_state_eq(load_bindings);
+    let show_bindings = use_state_eq(|| false);
+    //ação esperando a próxima tecla ou botão para ser associada
+    let rebinding = use_state_eq(|| None::<Action>);
+    //erro do webgl que impediu o jogo de começar
+    let gl_error = use_state_eq(|| None::<String>);
+    //o navegador tirou o contexto do webgl (aba em segundo plano no celular, driver reiniciado)
+    let gl_lost = use_state_eq(|| false);
 
     let canvas = use_node_ref();
 
-    use_effect({
-        let game = game.clone();
-        let canvas = canvas.clone();
-        move || {
-            let canvas: HtmlCanvasElement = canvas.cast::<HtmlCanvasElement>().unwrap().clone();
-            *game.borrow_mut() = Some(Game::new(canvas))
-        }
-    });
+    use_effect_with_deps(
+        {
+            let game = game.clone();
+            let canvas = canvas.clone();
+            let bindings = bindings.clone();
+            let gl_error = gl_error.clone();
+            move |_| {
+                let canvas: HtmlCanvasElement = canvas.cast::<HtmlCanvasElement>().unwrap().clone();
+                //só a primeira partida usa uma seed aleatória, as próximas derivam dela
+                match Game::new(canvas, random_seed()) {
+                    Ok(mut new_game) => {
+                        new_game.controls = Controls::new((*bindings).clone());
+                        *game.borrow_mut() = Some(new_game)
+                    }
+                    //sem jogo os eventos não fazem nada, só mostra o erro
+                    Err(err) => {
+                        log::error!("{}", err);
+                        gl_error.set(Some(err.to_string()));
+                    }
+                }
+            }
+        },
+        (),
+    );
+
+    //quando o contexto volta os programas e buffers são refeitos e o jogo continua de onde parou
+    use_effect_with_deps(
+        {
+            let game = game.clone();
+            let canvas = canvas.clone();
+            let gl_error = gl_error.clone();
+            let gl_lost = gl_lost.clone();
+            move |_| {
+                let canvas: HtmlCanvasElement = canvas.cast::<HtmlCanvasElement>().unwrap();
+
+                let lost_event: Closure<dyn Fn(Event)> = Closure::wrap(Box::new({
+                    let game = game.clone();
+                    let gl_lost = gl_lost.clone();
+                    move |event: Event| {
+                        //sem isso o navegador não devolve o contexto
+                        event.prevent_default();
+                        if let Some(game) = game.borrow_mut().as_mut() {
+                            game.lose_context();
+                        }
+                        gl_lost.set(true);
+                    }
+                }));
+                let restored_event: Closure<dyn Fn(Event)> = Closure::wrap(Box::new(move |_| {
+                    if let Some(game) = game.borrow_mut().as_mut() {
+                        if let Err(err) = game.restore_context() {
+                            log::error!("{}", err);
+                            gl_error.set(Some(err.to_string()));
+                        }
+                    }
+                    gl_lost.set(false);
+                }));
+
+                canvas
+                    .add_event_listener_with_callback(
+                        "webglcontextlost",
+                        lost_event.as_ref().unchecked_ref(),
+                    )
+                    .unwrap();
+                canvas
+                    .add_event_listener_with_callback(
+                        "webglcontextrestored",
+                        restored_event.as_ref().unchecked_ref(),
+                    )
+                    .unwrap();
+
+                move || {
+                    canvas
+                        .remove_event_listener_with_callback(
+                            "webglcontextlost",
+                            lost_event.as_ref().unchecked_ref(),
+                        )
+                        .unwrap();
+                    canvas
+                        .remove_event_listener_with_callback(
+                            "webglcontextrestored",
+                            restored_event.as_ref().unchecked_ref(),
+                        )
+                        .unwrap();
+                }
+            }
+        },
+        (),
+    );
 
     //Função de animação chamada a cada frame do navegador
     use_framerate({
         let last_time = Rc::new(RefCell::new(0f64));
         let game = game.clone();
+        let state = state.clone();
+        let lives = lives.clone();
+        let score = score.clone();
+        let multiplier = multiplier.clone();
+        let effects = effects.clone();
+        let energy = energy.clone();
+        let hyperspace_ready = hyperspace_ready.clone();
+        let playback = playback.clone();
+        let has_replay = has_replay.clone();
+        let replay_error = replay_error.clone();
+        let scheme = scheme.clone();
+        let last_state = Rc::new(Cell::new(GameState::Title));
 
         move |time: f64| {
             let mut game = game.borrow_mut();
-            let game = game.as_mut().unwrap();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
 
             let delta = {
                 let mut last_time = last_time.borrow_mut();
@@ -52,35 +229,104 @@ pub fn Asteroid() -> Html {
                 delta
             };
 
+            //controles não geram eventos, são lidos a cada frame
+            //o stick só gira a nave no esquema do teclado, então ele é escolhido ao usar o controle
+            if game.controls.poll(read_gamepads()) && game.scheme() == ControlScheme::Mouse {
+                game.set_scheme(ControlScheme::Keyboard);
+                scheme.set(ControlScheme::Keyboard);
+            }
+
             //Game loop
-            game.game_loop(time, delta);
+            game.game_loop(delta);
+            state.set(game.world.state());
+            lives.set(game.world.lives());
+            score.set(game.world.score());
+            multiplier.set(game.world.multiplier());
+            effects.set(
+                game.world
+                    .effects()
+                    .iter()
+                    .map(|x| (x.kind.name(), (x.time_left / 1000.0).ceil() as u32))
+                    .collect(),
+            );
+            energy.set((game.world.energy() * 100.0).round() as u32);
+            hyperspace_ready.set(game.world.hyperspace_cooldown() <= 0.0);
+            playback.set(game.world.playback().map(|x| (x.tick(), x.replay().len())));
+
+            //salva a partida assim que ela termina
+            let game_over = game.world.state() == GameState::GameOver;
+            let live = game.world.playback().is_none();
+            if game_over && last_state.get() != GameState::GameOver && live {
+                match save_replay(&game.world.replay().to_json()) {
+                    Ok(()) => {
+                        has_replay.set(true);
+                        replay_error.set(None);
+                    }
+                    Err(err) => replay_error.set(Some(format!("Replay not saved: {}", err))),
+                }
+            }
+            last_state.set(game.world.state());
         }
     });
 
+    //associa a entrada capturada à ação que estava esperando
+    let rebind = {
+        let game = game.clone();
+        let bindings = bindings.clone();
+        let rebinding = rebinding.clone();
+        move |input: PhysicalInput| -> bool {
+            let Some(action) = *rebinding else {
+                return false;
+            };
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return false;
+            };
+
+            //Esc só cancela
+            if input != PhysicalInput::Key("Escape".to_string()) {
+                game.controls.release_all();
+                game.controls.bindings.bind(input, action);
+                save_bindings(&game.controls.bindings);
+                bindings.set(game.controls.bindings.clone());
+            }
+            rebinding.set(None);
+            true
+        }
+    };
+
     let m_event = {
         let game = game.clone();
         move |event: MouseEvent| {
             let mut game = game.borrow_mut();
-            let game = game.as_mut().unwrap();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
 
-            game.input.mouse.pos = Point2::new(event.offset_x() as f64, event.offset_y() as f64);
-            game.update_input();
+            game.set_pointer(Point2::new(
+                event.offset_x() as f64,
+                event.offset_y() as f64,
+            ));
         }
     };
 
     let m_down_event = {
         let game = game.clone();
+        let rebind = rebind.clone();
         move |event: MouseEvent| {
+            if rebind(PhysicalInput::Mouse(event.button())) {
+                return;
+            }
             let mut game = game.borrow_mut();
-            let game = game.as_mut().unwrap();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
 
-            game.input.mouse.pos = Point2::new(event.offset_x() as f64, event.offset_y() as f64);
-            match event.button() {
-                0 => game.input.mouse.left = true,
-                2 => game.input.mouse.right = true,
-                _ => {}
-            }
-            game.update_input();
+            game.set_pointer(Point2::new(
+                event.offset_x() as f64,
+                event.offset_y() as f64,
+            ));
+            game.controls.press(PhysicalInput::Mouse(event.button()));
         }
     };
 
@@ -88,15 +334,98 @@ pub fn Asteroid() -> Html {
         let game = game.clone();
         move |event: MouseEvent| {
             let mut game = game.borrow_mut();
-            let game = game.as_mut().unwrap();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+
+            game.set_pointer(Point2::new(
+                event.offset_x() as f64,
+                event.offset_y() as f64,
+            ));
+            game.controls.release(&PhysicalInput::Mouse(event.button()));
+        }
+    };
+
+    //posição de cada toque que mudou, relativa ao canvas como o offset_x/offset_y do mouse
+    let changed_touches = {
+        let canvas = canvas.clone();
+        move |event: &TouchEvent| -> Vec<(i32, Point2<f64>)> {
+            let canvas: HtmlCanvasElement = canvas.cast::<HtmlCanvasElement>().unwrap();
+            let rect = canvas.get_bounding_client_rect();
+            let left = rect.left() + canvas.client_left() as f64;
+            let top = rect.top() + canvas.client_top() as f64;
+
+            let touches = event.changed_touches();
+            (0..touches.length())
+                .filter_map(|i| touches.get(i))
+                .map(|x| {
+                    let pos = Point2::new(x.client_x() as f64 - left, x.client_y() as f64 - top);
+                    (x.identifier(), pos)
+                })
+                .collect()
+        }
+    };
+
+    let t_start_event = {
+        let game = game.clone();
+        let changed_touches = changed_touches.clone();
+        let scheme = scheme.clone();
+        let touch_stick = touch_stick.clone();
+        let touch_used = touch_used.clone();
+        move |event: TouchEvent| {
+            //sem isso o navegador também gera eventos de mouse e rola a página
+            event.prevent_default();
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+
+            for (id, pos) in changed_touches(&event) {
+                game.touch_start(id, pos);
+            }
+            //o joystick mira como o mouse
+            if game.scheme() != ControlScheme::Mouse {
+                game.set_scheme(ControlScheme::Mouse);
+                scheme.set(ControlScheme::Mouse);
+            }
+            touch_stick.set(game.touch.stick());
+            touch_used.set(true);
+        }
+    };
+
+    let t_move_event = {
+        let game = game.clone();
+        let changed_touches = changed_touches.clone();
+        let touch_stick = touch_stick.clone();
+        move |event: TouchEvent| {
+            event.prevent_default();
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
 
-            game.input.mouse.pos = Point2::new(event.offset_x() as f64, event.offset_y() as f64);
-            match event.button() {
-                0 => game.input.mouse.left = false,
-                2 => game.input.mouse.right = false,
-                _ => {}
+            for (id, pos) in changed_touches(&event) {
+                game.touch_move(id, pos);
             }
-            game.update_input();
+            touch_stick.set(game.touch.stick());
+        }
+    };
+
+    //serve para o touchend e o touchcancel
+    let t_end_event = {
+        let game = game.clone();
+        let touch_stick = touch_stick.clone();
+        move |event: TouchEvent| {
+            event.prevent_default();
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+
+            for (id, _) in changed_touches(&event) {
+                game.touch_end(id);
+            }
+            touch_stick.set(game.touch.stick());
         }
     };
 
@@ -104,18 +433,189 @@ pub fn Asteroid() -> Html {
         event.prevent_default();
     };
 
+    //evita que os botões fiquem com o foco, senão o Enter do jogo também os aciona
+    let prevent_focus = |event: MouseEvent| {
+        event.prevent_default();
+    };
+
+    let watch_replay = {
+        let game = game.clone();
+        let replay_error = replay_error.clone();
+        move |_: MouseEvent| {
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+
+            match load_replay().map(|json| Replay::from_json(&json)) {
+                Some(Ok(replay)) => game.play_replay(replay),
+                Some(Err(err)) => {
+                    log::warn!("{}", err);
+                    replay_error.set(Some(format!("Replay not loaded: {}", err)));
+                }
+                None => {}
+            }
+        }
+    };
+
+    let exit_replay = {
+        let game = game.clone();
+        move |_: MouseEvent| {
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+            game.stop_replay(random_seed());
+        }
+    };
+
+    let toggle_follow = {
+        let game = game.clone();
+        let follow = follow.clone();
+        move |_: MouseEvent| {
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+
+            game.camera.follow = !game.camera.follow;
+            follow.set(game.camera.follow);
+        }
+    };
+
+    let zoom_by = {
+        let game = game.clone();
+        let zoom = zoom.clone();
+        move |factor: f64| {
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+
+            game.camera.zoom_by(factor);
+            zoom.set((game.camera.zoom() * 100.0).round() as u32);
+        }
+    };
+
+    let zoom_button = |factor: f64| {
+        let zoom_by = zoom_by.clone();
+        move |_: MouseEvent| zoom_by(factor)
+    };
+
+    //a roda do mouse também muda o zoom, sem rolar a página
+    let w_event = {
+        let zoom_by = zoom_by.clone();
+        move |event: WheelEvent| {
+            event.prevent_default();
+            zoom_by((-event.delta_y() * 0.001).exp());
+        }
+    };
+
+    let toggle_scheme = {
+        let game = game.clone();
+        let scheme = scheme.clone();
+        move |_: MouseEvent| {
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+
+            let next = match *scheme {
+                ControlScheme::Mouse => ControlScheme::Keyboard,
+                ControlScheme::Keyboard => ControlScheme::Mouse,
+            };
+            game.set_scheme(next);
+            scheme.set(next);
+        }
+    };
+
+    let set_speed = |speed: u32| {
+        let game = game.clone();
+        move |_: MouseEvent| {
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+            game.speed = speed;
+        }
+    };
+
+    let seek = {
+        let game = game.clone();
+        move |event: InputEvent| {
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+
+            let input: HtmlInputElement = event.target_unchecked_into();
+            if let Ok(tick) = input.value().parse::<usize>() {
+                game.world.seek(tick);
+            }
+        }
+    };
+
+    let start_rebinding = |action: Action| {
+        let rebinding = rebinding.clone();
+        move |_: MouseEvent| rebinding.set(Some(action))
+    };
+
+    let clear_binding = |action: Action| {
+        let game = game.clone();
+        let bindings = bindings.clone();
+        move |_: MouseEvent| {
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+
+            game.controls.release_all();
+            game.controls.bindings.clear(action);
+            save_bindings(&game.controls.bindings);
+            bindings.set(game.controls.bindings.clone());
+        }
+    };
+
+    let reset_bindings = {
+        let game = game.clone();
+        let bindings = bindings.clone();
+        move |_: MouseEvent| {
+            let mut game = game.borrow_mut();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
+
+            game.controls = Controls::new(Bindings::default());
+            save_bindings(&game.controls.bindings);
+            bindings.set(game.controls.bindings.clone());
+        }
+    };
+
+    let toggle_bindings = {
+        let show_bindings = show_bindings.clone();
+        let rebinding = rebinding.clone();
+        move |_: MouseEvent| {
+            show_bindings.set(!*show_bindings);
+            rebinding.set(None);
+        }
+    };
+
     let k_down_event = {
         let game = game.clone();
         move |event: KeyboardEvent| {
+            let input = PhysicalInput::Key(event.code());
+            if rebind(input.clone()) {
+                event.prevent_default();
+                return;
+            }
             let mut game = game.borrow_mut();
-            let game = game.as_mut().unwrap();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
 
-            if event.key() == " " {
-                if let ButtonState::Pressed = game.input.keyboard.space {
-                    game.input.keyboard.space = ButtonState::Hold;
-                } else {
-                    game.input.keyboard.space = ButtonState::Pressed;
-                }
+            //evita que as setas e o espaço rolem a página
+            if game.controls.press(input) {
+                event.prevent_default();
             }
         }
     };
@@ -124,24 +624,25 @@ pub fn Asteroid() -> Html {
         let game = game.clone();
         move |event: KeyboardEvent| {
             let mut game = game.borrow_mut();
-            let game = game.as_mut().unwrap();
+            let Some(game) = game.as_mut() else {
+                return;
+            };
 
-            if event.key() == " " {
-                game.input.keyboard.space = ButtonState::Released;
+            if game.controls.release(&PhysicalInput::Key(event.code())) {
+                event.prevent_default();
             }
         }
     };
 
-    // let k_hold_event = {
-    //     let game = game.clone();
-    //     move |event: KeyboardEvent| {
-    //         let mut game = game.borrow_mut();
-    //         let game = game.as_mut().unwrap();
-    //         if event.key() == " " {
-    //             game.input.keyboard.space = ButtonState::Hold;
-    //         }
-    //     }
-    // };
+    //sem o foco os keyup não chegam, então tudo é solto
+    let blur_event = {
+        let game = game.clone();
+        move |_: FocusEvent| {
+            if let Some(game) = game.borrow_mut().as_mut() {
+                game.controls.release_all();
+            }
+        }
+    };
 
     use_effect({
         let k_down_event: Closure<dyn Fn(KeyboardEvent)> = Closure::wrap(Box::new(k_down_event));
@@ -183,15 +684,181 @@ pub fn Asteroid() -> Html {
         }
     });
 
+    use_effect({
+        let blur_event: Closure<dyn Fn(FocusEvent)> = Closure::wrap(Box::new(blur_event));
+        move || {
+            window()
+                .unwrap()
+                .add_event_listener_with_callback("blur", blur_event.as_ref().unchecked_ref())
+                .unwrap();
+
+            move || {
+                window()
+                    .unwrap()
+                    .remove_event_listener_with_callback(
+                        "blur",
+                        blur_event.as_ref().unchecked_ref(),
+                    )
+                    .unwrap();
+            }
+        }
+    });
+
+    let (scheme_text, controls_help) = match *scheme {
+        ControlScheme::Mouse => (
+            "Controls: mouse",
+            "Left button thrusts, right button fires, Down or H jumps, Shift shields",
+        ),
+        ControlScheme::Keyboard => (
+            "Controls: keyboard",
+            "Arrows or WASD steer, Space or Ctrl fires, Down jumps, Shift shields",
+        ),
+    };
+
+    let overlay = match *state {
+        _ if gl_error.is_some() => Some(html! {
+            <>
+                <h1>{"ERROR"}</h1>
+                <p>{"The game could not start"}</p>
+                <p class="help">{gl_error.as_deref().unwrap_or_default()}</p>
+            </>
+        }),
+        _ if *gl_lost => Some(html! {
+            <>
+                <h1>{"PAUSED"}</h1>
+                <p>{"Waiting for the graphics to come back"}</p>
+            </>
+        }),
+        GameState::Title => Some(html! {
+            <>
+                <h1>{"ASTEROIDS"}</h1>
+                <p>{"Press Enter or tap to start"}</p>
+                <p class="help">{controls_help}</p>
+                <p class="help">{"Gamepad: left stick steers, A fires, B jumps, RB shields"}</p>
+                if let Some(err) = &*replay_error {
+                    <p class="error">{err}</p>
+                }
+            </>
+        }),
+        GameState::Paused => Some(html! {
+            <>
+                <h1>{"PAUSED"}</h1>
+                <p>{"Press Esc or P, or tap to resume"}</p>
+            </>
+        }),
+        GameState::GameOver => Some(html! {
+            <>
+                <h1>{"GAME OVER"}</h1>
+                <p>{"Press Enter or tap to continue"}</p>
+                if let Some(err) = &*replay_error {
+                    <p class="error">{err}</p>
+                }
+            </>
+        }),
+        GameState::Playing | GameState::Dying => None,
+    };
+
+    let lives_text = "♦".repeat(*lives as usize);
+    let score_text = if *multiplier > 1 {
+        format!("{} x{}", *score, *multiplier)
+    } else {
+        score.to_string()
+    };
+
     html! {
-        <>
+        <div class="asteroid">
             <canvas
             oncontextmenu={prevent_context}
             onmousedown={m_down_event.clone()}
             onmouseup={m_up_event.clone()}
             onmousemove={m_event.clone()}
-            style="border: 1px solid"
-            ref={canvas} width="600" height="600" />
-        </>
+            onwheel={w_event}
+            ontouchstart={t_start_event}
+            ontouchmove={t_move_event}
+            ontouchend={t_end_event.clone()}
+            ontouchcancel={t_end_event}
+            ref={canvas} />
+            if !matches!(*state, GameState::Title) {
+                <div class="hud">
+                    <span>{score_text}</span>
+                    <span class="effects">
+                        { for effects.iter().map(|(name, time)| html! {
+                            <span>{format!("{} {}s", name, time)}</span>
+                        }) }
+                    </span>
+                    <span>{lives_text}</span>
+                </div>
+                <div class="abilities">
+                    <span class={classes!(hyperspace_ready.then_some("ready"))}>{"HYPERSPACE"}</span>
+                    <span class="energy">
+                        <span style={format!("width: {}%", *energy)} />
+                    </span>
+                </div>
+            }
+            if let Some((origin, pos)) = *touch_stick {
+                <div class="touch-stick" style={format!("left: {}px; top: {}px", origin.x, origin.y)}>
+                    <span style={format!("left: {}px; top: {}px", pos.x - origin.x, pos.y - origin.y)} />
+                </div>
+            }
+            if *touch_used {
+                <div class="touch-fire">{"FIRE"}</div>
+            }
+            if let Some(overlay) = overlay {
+                <div class="overlay">{overlay}</div>
+            }
+            if *show_bindings {
+                <div class="bindings">
+                    { for Action::ALL.into_iter().map(|action| {
+                        let inputs = bindings
+                            .inputs(action)
+                            .map(|x| x.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        html! {
+                            <div class="binding">
+                                <span class="action">{action.name()}</span>
+                                <span class="inputs">{inputs}</span>
+                                if *rebinding == Some(action) {
+                                    <span class="waiting">{"press a key or button, Esc cancels"}</span>
+                                } else {
+                                    <button onmousedown={prevent_focus} onclick={start_rebinding(action)}>{"+"}</button>
+                                    <button onmousedown={prevent_focus} onclick={clear_binding(action)}>{"Clear"}</button>
+                                }
+                            </div>
+                        }
+                    }) }
+                    <button onmousedown={prevent_focus} onclick={reset_bindings}>{"Reset to defaults"}</button>
+                </div>
+            }
+            <div class="controls">
+                if let Some((tick, len)) = *playback {
+                    <span>{"REPLAY"}</span>
+                    <button onmousedown={prevent_focus} onclick={set_speed(1)}>{"1x"}</button>
+                    <button onmousedown={prevent_focus} onclick={set_speed(2)}>{"2x"}</button>
+                    <button onmousedown={prevent_focus} onclick={set_speed(4)}>{"4x"}</button>
+                    <input type="range" min="0" max={len.to_string()}
+                        value={tick.to_string()} oninput={seek} />
+                    <button onmousedown={prevent_focus} onclick={exit_replay}>{"Exit"}</button>
+                } else {
+                    <button onmousedown={prevent_focus} onclick={toggle_scheme}>
+                        {scheme_text}
+                    </button>
+                    <button onmousedown={prevent_focus} onclick={toggle_bindings}>
+                        {"Bindings"}
+                    </button>
+                    if *has_replay && matches!(*state, GameState::Title | GameState::GameOver) {
+                        <button onmousedown={prevent_focus} onclick={watch_replay}>
+                            {"Watch last replay"}
+                        </button>
+                    }
+                }
+                <button onmousedown={prevent_focus} onclick={toggle_follow}>
+                    {if *follow { "Camera: follow" } else { "Camera: fixed" }}
+                </button>
+                <button onmousedown={prevent_focus} onclick={zoom_button(1.0 / 1.25)}>{"-"}</button>
+                <span>{format!("{}%", *zoom)}</span>
+                <button onmousedown={prevent_focus} onclick={zoom_button(1.25)}>{"+"}</button>
+            </div>
+        </div>
     }
 }
@@ -2,11 +2,19 @@
 
 precision mediump float;
 attribute vec2 vert_position;
+//per instance, see `Instance`
 attribute vec2 offset;
-attribute vec2 dimm;
 attribute float rot;
 attribute float scale;
 attribute vec3 color;
+//size of the model before scaling, the same for all its instances
+uniform vec2 dimentions;
+//world units to clip space, depends on the size of the canvas
+uniform vec2 projection;
+//world to view coordinates, see `Camera`
+uniform mat3 view;
+//canvas pixels per css pixel, keeps the points the same size on hidpi screens
+uniform float pixel_ratio;
 
 varying vec3 fragColor;
 
@@ -23,15 +31,16 @@ void main()
 	vec2 pos = vert_position;
 	
 	pos *= scale;
-	gl_PointSize = scale;
+	gl_PointSize = scale * pixel_ratio;
 
+	vec2 dimm = dimentions * scale;
 	pos -= dimm / 2.0;
 	pos = rotationMatrix * pos;
 	pos += dimm / 2.0;
 
 	pos += offset;
 
-	pos /= 1000.0;
+	pos = (view * vec3(pos, 1.0)).xy * projection;
 
 	gl_Position = vec4( pos, 0.0, 1.0 );
 }
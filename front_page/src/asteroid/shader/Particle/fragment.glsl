@@ -0,0 +1,9 @@
+
+precision mediump float;
+
+varying vec3 fragColor;
+
+void main()
+{
+    gl_FragColor=vec4(fragColor, 1.0);
+}
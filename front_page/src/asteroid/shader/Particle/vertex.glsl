@@ -0,0 +1,18 @@
+
+
+precision mediump float;
+attribute vec2 vert_position;
+attribute vec3 vert_color;
+uniform vec2 projection;
+uniform float pixel_ratio;
+//world to view coordinates, see `Camera`
+uniform mat3 view;
+
+varying vec3 fragColor;
+
+void main()
+{
+	fragColor = vert_color;
+	gl_PointSize = 4.0 * pixel_ratio;
+	gl_Position = vec4( (view * vec3(vert_position, 1.0)).xy * projection, 0.0, 1.0 );
+}
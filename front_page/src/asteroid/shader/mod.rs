@@ -1,3 +1,7 @@
+mod program;
+
+pub use program::*;
+
 pub fn get_vertex_shader() -> &'static str {
     include_str!("Object/vertex_shader.glsl")
 }
@@ -13,3 +17,11 @@ pub fn get_vertex_shader_teste() -> &'static str {
 pub fn get_fragment_shader_teste() -> &'static str {
     include_str!("Teste/fragment.glsl")
 }
+
+pub fn get_vertex_shader_particle() -> &'static str {
+    include_str!("Particle/vertex.glsl")
+}
+
+pub fn get_fragment_shader_particle() -> &'static str {
+    include_str!("Particle/fragment.glsl")
+}
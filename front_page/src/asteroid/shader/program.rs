@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use web_sys::{WebGlProgram, WebGlRenderingContext, WebGlShader, WebGlUniformLocation};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShaderError {
+    //the context couldn't create a shader or program, usually because it was lost
+    Create,
+    //same for a vertex or instance buffer
+    Buffer,
+    Compile { kind: &'static str, log: String },
+    Link(String),
+    //asked for an attribute the program doesn't have (or that the compiler removed)
+    Attribute(String),
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Create => {
+                write!(f, "could not create a webgl shader, is the context lost?")
+            }
+            ShaderError::Buffer => {
+                write!(f, "could not create a webgl buffer, is the context lost?")
+            }
+            ShaderError::Compile { kind, log } => {
+                write!(f, "{} shader did not compile: {}", kind, log)
+            }
+            ShaderError::Link(log) => write!(f, "shader program did not link: {}", log),
+            ShaderError::Attribute(name) => write!(f, "shader program has no attribute {}", name),
+        }
+    }
+}
+
+//a linked program with the locations of its active attributes and uniforms, looked up once
+//the program is deleted from the gpu when this is dropped
+#[derive(Debug)]
+pub struct ShaderProgram {
+    gl: WebGlRenderingContext,
+    prg: WebGlProgram,
+    attributes: HashMap<String, u32>,
+    uniforms: HashMap<String, WebGlUniformLocation>,
+}
+
+fn compile(
+    gl: &WebGlRenderingContext,
+    kind: u32,
+    source: &str,
+) -> Result<WebGlShader, ShaderError> {
+    let shader = gl.create_shader(kind).ok_or(ShaderError::Create)?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+
+    let compiled = gl
+        .get_shader_parameter(&shader, WebGlRenderingContext::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false);
+    if compiled {
+        return Ok(shader);
+    }
+
+    let log = gl.get_shader_info_log(&shader).unwrap_or_default();
+    gl.delete_shader(Some(&shader));
+    let kind = match kind {
+        WebGlRenderingContext::VERTEX_SHADER => "vertex",
+        _ => "fragment",
+    };
+    Err(ShaderError::Compile { kind, log })
+}
+
+impl ShaderProgram {
+    pub fn new(
+        gl: &WebGlRenderingContext,
+        vertex_source: &str,
+        fragment_source: &str,
+    ) -> Result<ShaderProgram, ShaderError> {
+        let vertex_shader = compile(gl, WebGlRenderingContext::VERTEX_SHADER, vertex_source)?;
+        let fragment_shader =
+            match compile(gl, WebGlRenderingContext::FRAGMENT_SHADER, fragment_source) {
+                Ok(shader) => shader,
+                Err(err) => {
+                    gl.delete_shader(Some(&vertex_shader));
+                    return Err(err);
+                }
+            };
+
+        let prg = gl.create_program();
+        if let Some(prg) = &prg {
+            gl.attach_shader(prg, &vertex_shader);
+            gl.attach_shader(prg, &fragment_shader);
+            gl.link_program(prg);
+        }
+
+        //the program keeps what it needs, the shaders are not used again, whether it linked or not
+        gl.delete_shader(Some(&vertex_shader));
+        gl.delete_shader(Some(&fragment_shader));
+        let prg = prg.ok_or(ShaderError::Create)?;
+
+        let linked = gl
+            .get_program_parameter(&prg, WebGlRenderingContext::LINK_STATUS)
+            .as_bool()
+            .unwrap_or(false);
+        if !linked {
+            let log = gl.get_program_info_log(&prg).unwrap_or_default();
+            gl.delete_program(Some(&prg));
+            return Err(ShaderError::Link(log));
+        }
+
+        let count = |kind| gl.get_program_parameter(&prg, kind).as_f64().unwrap_or(0.0) as u32;
+
+        let attributes = (0..count(WebGlRenderingContext::ACTIVE_ATTRIBUTES))
+            .filter_map(|i| gl.get_active_attrib(&prg, i))
+            .map(|info| {
+                let location = gl.get_attrib_location(&prg, &info.name());
+                (info.name(), location as u32)
+            })
+            .collect();
+
+        let uniforms = (0..count(WebGlRenderingContext::ACTIVE_UNIFORMS))
+            .filter_map(|i| gl.get_active_uniform(&prg, i))
+            .filter_map(|info| {
+                let location = gl.get_uniform_location(&prg, &info.name())?;
+                Some((info.name(), location))
+            })
+            .collect();
+
+        Ok(ShaderProgram {
+            gl: gl.clone(),
+            prg,
+            attributes,
+            uniforms,
+        })
+    }
+
+    pub fn use_program(&self, gl: &WebGlRenderingContext) {
+        gl.use_program(Some(&self.prg));
+    }
+
+    pub fn attribute(&self, name: &str) -> Result<u32, ShaderError> {
+        self.attributes
+            .get(name)
+            .copied()
+            .ok_or_else(|| ShaderError::Attribute(name.to_string()))
+    }
+
+    //None for uniforms the compiler removed, setting those does nothing
+    pub fn uniform(&self, name: &str) -> Option<&WebGlUniformLocation> {
+        self.uniforms.get(name)
+    }
+}
+
+impl Drop for ShaderProgram {
+    fn drop(&mut self) {
+        self.gl.delete_program(Some(&self.prg));
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use nalgebra::{Point2, Vector2};
+use trunk_template::sim::{stick, Controls, PhysicalInput, TouchInput, STICK_THRESHOLD};
+
+//how far (in canvas pixels) the finger goes from where it landed to push the stick all the way
+pub const STICK_RADIUS: f64 = 50.0;
+
+//on screen controls: a virtual joystick where a finger lands on the left half of the canvas
+//and a fire button anywhere on the right half, each finger is tracked on its own
+#[derive(Debug, Default)]
+pub struct TouchControls {
+    //finger id to the control it holds
+    fingers: HashMap<i32, TouchInput>,
+    //where the stick finger landed and where it is now, in canvas pixels
+    stick: Option<(Point2<f64>, Point2<f64>)>,
+    //last direction the stick was pushed to (canvas pixels, y down), kept after letting go
+    aim: Option<Vector2<f64>>,
+}
+
+impl TouchControls {
+    pub fn start(&mut self, id: i32, pos: Point2<f64>, canvas_width: f64) {
+        let control = if pos.x < canvas_width / 2.0 {
+            TouchInput::Stick
+        } else {
+            TouchInput::FireButton
+        };
+        //a second finger on the same half does nothing
+        if self.fingers.values().any(|x| *x == control) {
+            return;
+        }
+        self.fingers.insert(id, control);
+        if control == TouchInput::Stick {
+            self.stick = Some((pos, pos));
+        }
+    }
+
+    pub fn move_to(&mut self, id: i32, pos: Point2<f64>) {
+        if self.fingers.get(&id) != Some(&TouchInput::Stick) {
+            return;
+        }
+        if let Some((origin, _)) = self.stick {
+            self.stick = Some((origin, pos));
+            let offset = (pos - origin) / STICK_RADIUS;
+            let value = stick(offset.x, offset.y);
+            if value != Vector2::zeros() {
+                self.aim = Some(value);
+            }
+        }
+    }
+
+    pub fn end(&mut self, id: i32) {
+        if self.fingers.remove(&id) == Some(TouchInput::Stick) {
+            self.stick = None;
+        }
+    }
+
+    //the touch controls are dropped as soon as the mouse is used
+    pub fn clear(&mut self) {
+        self.fingers.clear();
+        self.stick = None;
+        self.aim = None;
+    }
+
+    pub fn stick(&self) -> Option<(Point2<f64>, Point2<f64>)> {
+        self.stick
+    }
+
+    pub fn aim(&self) -> Option<Vector2<f64>> {
+        self.aim
+    }
+
+    //presses or releases the touch inputs to match the fingers on the screen
+    pub fn apply(&self, controls: &mut Controls) {
+        let pushed = self.stick.is_some_and(|(origin, pos)| {
+            let offset = (pos - origin) / STICK_RADIUS;
+            stick(offset.x, offset.y).norm() > STICK_THRESHOLD
+        });
+        let firing = self.fingers.values().any(|x| *x == TouchInput::FireButton);
+
+        for (control, down) in [
+            (TouchInput::Stick, pushed),
+            (TouchInput::FireButton, firing),
+        ] {
+            let input = PhysicalInput::Touch(control);
+            if down {
+                controls.press(input);
+            } else {
+                controls.release(&input);
+            }
+        }
+    }
+}
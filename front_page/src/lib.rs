@@ -0,0 +1,7 @@
+//! Asteroid game logic.
+//!
+//! Everything in `sim` is plain rust: no `web_sys`, no webgl. The browser front end
+//! (the `trunk-template` binary) draws a `sim::World` and feeds it input, while tests
+//! can run whole games natively with `cargo test`.
+
+pub mod sim;
@@ -0,0 +1,112 @@
+use nalgebra::{Point2, Vector2};
+
+//shortest vector from a to b on a map that wraps around on both axes
+pub fn wrapped_delta(a: Point2<f64>, b: Point2<f64>, map_dim: Vector2<f64>) -> Vector2<f64> {
+    let mut delta = b - a;
+    for i in 0..2 {
+        delta[i] -= (delta[i] / map_dim[i]).round() * map_dim[i];
+    }
+    delta
+}
+
+//what to add to b to get its copy closest to a, the narrowphase tests against that copy
+pub fn wrap_offset(a: Point2<f64>, b: Point2<f64>, map_dim: Vector2<f64>) -> Vector2<f64> {
+    a + wrapped_delta(a, b, map_dim) - b
+}
+
+//offsets (whole multiples of the map size) of every copy of a circle that overlaps the
+//rectangle from min to max, the map repeats forever on both axes
+pub fn wrapped_copies(
+    center: Point2<f64>,
+    radius: f64,
+    (min, max): (Point2<f64>, Point2<f64>),
+    map_dim: Vector2<f64>,
+) -> impl Iterator<Item = Vector2<f64>> {
+    let range = |i: usize| {
+        let first = ((min[i] - center[i] - radius) / map_dim[i]).ceil() as i32;
+        let last = ((max[i] - center[i] + radius) / map_dim[i]).floor() as i32;
+        first..=last
+    };
+    let (xs, ys) = (range(0), range(1));
+    xs.flat_map(move |x| {
+        ys.clone()
+            .map(move |y| Vector2::new(x as f64 * map_dim.x, y as f64 * map_dim.y))
+    })
+}
+
+//cheap test done before the real (SAT) collision test
+pub fn circles_overlap(
+    a: (Point2<f64>, f64),
+    b: (Point2<f64>, f64),
+    map_dim: Vector2<f64>,
+) -> bool {
+    let radius = a.1 + b.1;
+    wrapped_delta(a.0, b.0, map_dim).norm_squared() <= radius * radius
+}
+
+//uniform grid over the map, entities are inserted in every cell their bounding circle touches
+//cells past an edge of the map wrap to the other side, like the entities do
+#[derive(Debug, Clone)]
+pub struct Grid {
+    origin: Point2<f64>,
+    cell_size: f64,
+    cols: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new(map_dim: Vector2<f64>, cell_size: f64) -> Self {
+        let cols = (map_dim.x / cell_size).ceil().max(1.0) as usize;
+        let rows = (map_dim.y / cell_size).ceil().max(1.0) as usize;
+        Grid {
+            origin: Point2::origin() - map_dim / 2.0,
+            cell_size,
+            cols,
+            rows,
+            cells: vec![vec![]; cols * rows],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+    }
+
+    //calls f with the index of every cell touched by the circle, each one only once
+    fn for_each_cell(&self, center: Point2<f64>, radius: f64, mut f: impl FnMut(usize)) {
+        let min = (center - Vector2::repeat(radius) - self.origin) / self.cell_size;
+        let max = (center + Vector2::repeat(radius) - self.origin) / self.cell_size;
+
+        //a circle bigger than the map touches every cell
+        let span_x = (max.x.floor() - min.x.floor()) as i64 + 1;
+        let span_y = (max.y.floor() - min.y.floor()) as i64 + 1;
+        let span_x = span_x.min(self.cols as i64);
+        let span_y = span_y.min(self.rows as i64);
+
+        for y in 0..span_y {
+            let row = (min.y.floor() as i64 + y).rem_euclid(self.rows as i64) as usize;
+            for x in 0..span_x {
+                let col = (min.x.floor() as i64 + x).rem_euclid(self.cols as i64) as usize;
+                f(row * self.cols + col);
+            }
+        }
+    }
+
+    pub fn insert(&mut self, id: usize, center: Point2<f64>, radius: f64) {
+        let mut touched = vec![];
+        self.for_each_cell(center, radius, |cell| touched.push(cell));
+        for cell in touched {
+            self.cells[cell].push(id);
+        }
+    }
+
+    //ids sharing a cell with the circle, sorted and without repetitions
+    pub fn query(&self, center: Point2<f64>, radius: f64, out: &mut Vec<usize>) {
+        out.clear();
+        self.for_each_cell(center, radius, |cell| out.extend(&self.cells[cell]));
+        out.sort_unstable();
+        out.dedup();
+    }
+}
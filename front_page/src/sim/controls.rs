@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+
+use super::input::{Action, Actions};
+
+//stick positions closer to the center than this are read as the center
+pub const STICK_DEADZONE: f64 = 0.25;
+//how far past the deadzone the stick must go along an axis to count as a button
+pub const STICK_THRESHOLD: f64 = 0.5;
+
+//a key (by its `KeyboardEvent.code`, so it doesn't depend on the layout), a mouse button,
+//a gamepad button or stick direction (by their index in the standard gamepad mapping)
+//or one of the on screen touch controls
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PhysicalInput {
+    Key(String),
+    Mouse(i16),
+    PadButton(u8),
+    //axis and whether it is the positive side
+    PadAxis(u8, bool),
+    Touch(TouchInput),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TouchInput {
+    //the virtual joystick pushed past the deadzone
+    Stick,
+    FireButton,
+    //any touch while the game is not being played, it starts or resumes it
+    Screen,
+}
+
+impl PhysicalInput {
+    //gamepads have no events, their inputs are polled every frame
+    pub fn is_polled(&self) -> bool {
+        matches!(
+            self,
+            PhysicalInput::PadButton(_) | PhysicalInput::PadAxis(..)
+        )
+    }
+}
+
+//radial deadzone, outside of it the position is rescaled so it still goes from 0 to 1
+pub fn stick(x: f64, y: f64) -> Vector2<f64> {
+    let pos = Vector2::new(x, y);
+    let len = pos.norm();
+    if len <= STICK_DEADZONE {
+        return Vector2::zeros();
+    }
+    pos / len * ((len - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).min(1.0)
+}
+
+//the directions a stick is pushed to, as if each one were a button
+pub fn stick_inputs(first_axis: u8, x: f64, y: f64) -> impl Iterator<Item = PhysicalInput> {
+    let pos = stick(x, y);
+    [
+        (first_axis, false, -pos.x),
+        (first_axis, true, pos.x),
+        (first_axis + 1, false, -pos.y),
+        (first_axis + 1, true, pos.y),
+    ]
+    .into_iter()
+    .filter(|(_, _, value)| *value > STICK_THRESHOLD)
+    .map(|(axis, positive, _)| PhysicalInput::PadAxis(axis, positive))
+}
+
+impl fmt::Display for PhysicalInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhysicalInput::Key(code) => write!(f, "{}", code.trim_start_matches("Key")),
+            PhysicalInput::Mouse(0) => write!(f, "Left click"),
+            PhysicalInput::Mouse(1) => write!(f, "Middle click"),
+            PhysicalInput::Mouse(2) => write!(f, "Right click"),
+            PhysicalInput::Mouse(button) => write!(f, "Mouse {}", button),
+            PhysicalInput::PadButton(button) => {
+                //names of the standard mapping, the rest only by number
+                let names = [
+                    "A", "B", "X", "Y", "LB", "RB", "LT", "RT", "Back", "Start", "L3", "R3", "Up",
+                    "Down", "Left", "Right",
+                ];
+                match names.get(*button as usize) {
+                    Some(name) => write!(f, "Pad {}", name),
+                    None => write!(f, "Pad {}", button),
+                }
+            }
+            PhysicalInput::PadAxis(axis, positive) => {
+                let side = match (axis % 2, positive) {
+                    (0, false) => "left",
+                    (0, true) => "right",
+                    (_, false) => "up",
+                    (_, true) => "down",
+                };
+                let stick = if *axis < 2 {
+                    "Left stick"
+                } else {
+                    "Right stick"
+                };
+                write!(f, "{} {}", stick, side)
+            }
+            PhysicalInput::Touch(TouchInput::Stick) => write!(f, "Touch stick"),
+            PhysicalInput::Touch(TouchInput::FireButton) => write!(f, "Touch button"),
+            PhysicalInput::Touch(TouchInput::Screen) => write!(f, "Tap"),
+        }
+    }
+}
+
+//which physical inputs trigger each action, one input triggers at most one action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bindings(Vec<(PhysicalInput, Action)>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let key = |code: &str, action| (PhysicalInput::Key(code.to_string()), action);
+        Bindings(vec![
+            (PhysicalInput::Mouse(0), Action::Thrust),
+            (PhysicalInput::Mouse(2), Action::Fire),
+            key("ArrowUp", Action::Thrust),
+            key("KeyW", Action::Thrust),
+            key("Space", Action::Fire),
+            key("ControlLeft", Action::Fire),
+            key("ControlRight", Action::Fire),
+            key("ArrowLeft", Action::RotateLeft),
+            key("KeyA", Action::RotateLeft),
+            key("ArrowRight", Action::RotateRight),
+            key("KeyD", Action::RotateRight),
+            key("ArrowDown", Action::Hyperspace),
+            key("KeyS", Action::Hyperspace),
+            key("KeyH", Action::Hyperspace),
+            key("ShiftLeft", Action::Shield),
+            key("ShiftRight", Action::Shield),
+            key("KeyE", Action::Shield),
+            key("Escape", Action::Pause),
+            key("KeyP", Action::Pause),
+            key("Enter", Action::Start),
+            (PhysicalInput::PadAxis(1, false), Action::Thrust),
+            (PhysicalInput::PadAxis(0, false), Action::RotateLeft),
+            (PhysicalInput::PadAxis(0, true), Action::RotateRight),
+            (PhysicalInput::PadButton(12), Action::Thrust),
+            (PhysicalInput::PadButton(14), Action::RotateLeft),
+            (PhysicalInput::PadButton(15), Action::RotateRight),
+            (PhysicalInput::PadButton(0), Action::Fire),
+            (PhysicalInput::PadButton(7), Action::Fire),
+            (PhysicalInput::PadButton(1), Action::Hyperspace),
+            (PhysicalInput::PadButton(5), Action::Shield),
+            (PhysicalInput::PadButton(9), Action::Start),
+            (PhysicalInput::Touch(TouchInput::Stick), Action::Thrust),
+            (PhysicalInput::Touch(TouchInput::FireButton), Action::Fire),
+            (PhysicalInput::Touch(TouchInput::Screen), Action::Start),
+        ])
+    }
+}
+
+impl Bindings {
+    pub fn action(&self, input: &PhysicalInput) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(x, _)| x == input)
+            .map(|(_, action)| *action)
+    }
+
+    pub fn inputs(&self, action: Action) -> impl Iterator<Item = &PhysicalInput> {
+        self.0
+            .iter()
+            .filter(move |(_, x)| *x == action)
+            .map(|(input, _)| input)
+    }
+
+    //the input is taken away from the action it triggered before
+    pub fn bind(&mut self, input: PhysicalInput, action: Action) {
+        self.0.retain(|(x, _)| *x != input);
+        self.0.push((input, action));
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.0.retain(|(_, x)| *x != action);
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Bindings, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+//turns physical input events into action states, edges are detected once per frame in `latch`
+#[derive(Debug, Clone, Default)]
+pub struct Controls {
+    pub bindings: Bindings,
+    held: HashSet<PhysicalInput>,
+    //actions that went down since the last latch, so a press shorter than a frame still counts
+    tapped: HashSet<Action>,
+}
+
+impl Controls {
+    pub fn new(bindings: Bindings) -> Self {
+        Controls {
+            bindings,
+            ..Default::default()
+        }
+    }
+
+    //returns false for inputs bound to nothing
+    pub fn press(&mut self, input: PhysicalInput) -> bool {
+        let Some(action) = self.bindings.action(&input) else {
+            return false;
+        };
+        //keydown repeats while the key is held, only the first one is a press
+        if self.held.insert(input) {
+            self.tapped.insert(action);
+        }
+        true
+    }
+
+    pub fn release(&mut self, input: &PhysicalInput) -> bool {
+        self.held.remove(input);
+        self.bindings.action(input).is_some()
+    }
+
+    //the polled inputs that are down this frame, the ones missing are released
+    //returns true when something bound went down
+    pub fn poll(&mut self, inputs: impl IntoIterator<Item = PhysicalInput>) -> bool {
+        let down: HashSet<PhysicalInput> = inputs.into_iter().collect();
+        self.held.retain(|x| !x.is_polled() || down.contains(x));
+
+        let mut pressed = false;
+        for input in down {
+            if !self.held.contains(&input) {
+                pressed |= self.press(input);
+            }
+        }
+        pressed
+    }
+
+    //everything up, used when the page loses the focus and the key ups never arrive
+    pub fn release_all(&mut self) {
+        self.held.clear();
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        self.held
+            .iter()
+            .any(|x| self.bindings.action(x) == Some(action))
+    }
+
+    pub fn latch(&mut self, actions: &mut Actions) {
+        for action in Action::ALL {
+            let down = self.is_down(action);
+            let tapped = self.tapped.contains(&action);
+            actions[action] = actions[action].next(down, tapped);
+        }
+        self.tapped.clear();
+    }
+}
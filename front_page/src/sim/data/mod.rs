@@ -0,0 +1,73 @@
+pub fn get_ship() -> &'static str {
+    include_str!("ship.json")
+}
+
+pub fn get_bullet() -> &'static str {
+    include_str!("bullet.json")
+}
+
+pub fn get_asteroid() -> &'static str {
+    include_str!("asteroid.json")
+}
+
+pub fn get_saucer() -> &'static str {
+    include_str!("saucer.json")
+}
+
+pub fn get_powerup() -> &'static str {
+    include_str!("powerup.json")
+}
+
+pub fn get_shield() -> &'static str {
+    include_str!("shield.json")
+}
+
+// pub fn get_teste() -> &'static str {
+//     include_str!("teste.json")
+// }
+
+//every model the game knows how to spawn, also used as the key of its gpu resources
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Model {
+    #[default]
+    Ship,
+    Bullet,
+    Asteroid,
+    Saucer,
+    PowerUp,
+    //bubble drawn around the ship, it never collides
+    Shield,
+}
+
+impl Model {
+    pub const ALL: [Model; 6] = [
+        Model::Ship,
+        Model::Bullet,
+        Model::Asteroid,
+        Model::Saucer,
+        Model::PowerUp,
+        Model::Shield,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Model::Ship => "ship",
+            Model::Bullet => "bullet",
+            Model::Asteroid => "asteroid",
+            Model::Saucer => "saucer",
+            Model::PowerUp => "powerup",
+            Model::Shield => "shield",
+        }
+    }
+
+    pub fn json(&self) -> &'static str {
+        match self {
+            Model::Ship => get_ship(),
+            Model::Bullet => get_bullet(),
+            Model::Asteroid => get_asteroid(),
+            Model::Saucer => get_saucer(),
+            Model::PowerUp => get_powerup(),
+            Model::Shield => get_shield(),
+        }
+    }
+}
@@ -1,42 +1,43 @@
-extern crate nalgebra as na;
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    f64::consts::{PI, TAU},
+};
 
-use super::drawable::*;
+use super::{broadphase::wrap_offset, data::Model, object::Object, trig};
 
-use na::{Matrix2x3, Matrix2xX, Point2, Rotation2, Vector2, Vector3};
-use wasm_bindgen::JsValue;
-use web_sys::WebGlRenderingContext;
+use nalgebra::{Matrix2x3, Matrix2xX, Point2, Vector2, Vector3};
 
 #[derive(Debug, Clone)]
-pub struct EntityDrawable {
-    pub object: ObjectDrawable,
+pub struct Entity {
+    pub object: Object,
     pub pos: Point2<f64>,
+    //transform at the start of the last simulation step, used to interpolate the drawing
+    pub prev_pos: Point2<f64>,
+    pub prev_rotation: f64,
     pub speed: Vector2<f64>,
     pub acc: Vector2<f64>,
     pub color: Vector3<f64>,
     pub rotation: f64,
-    pub delete_on_out_of_bounds: bool,
     pub max_speed_sqr: f64,
 }
 
-impl EntityDrawable {
-    pub fn load_gl(gl: &WebGlRenderingContext, json: &str) -> EntityDrawable {
-        let entity: Object = json.try_into().unwrap();
-
-        EntityDrawable {
-            object: entity.load_gl(gl),
+impl Entity {
+    pub fn new(model: Model) -> Entity {
+        Entity {
+            object: Object::load(model),
             pos: Point2::default(),
+            prev_pos: Point2::default(),
+            prev_rotation: Default::default(),
             speed: Vector2::default(),
             rotation: Default::default(),
             acc: Vector2::default(),
-            delete_on_out_of_bounds: true,
             max_speed_sqr: Default::default(),
             color: Vector3::new(1.0, 0.0, 1.0),
         }
     }
 }
 
-impl EntityDrawable {
+impl Entity {
     pub fn update_physics(&mut self, delta: f64) {
         self.speed += self.acc * delta;
         if self.max_speed_sqr != 0.0 && self.speed.norm_squared() > self.max_speed_sqr {
@@ -49,16 +50,42 @@ impl EntityDrawable {
         self.pos + self.object.dimentions() / 2.0
     }
 
-    pub fn draw(&self, context: &WebGlRenderingContext) -> Result<(), JsValue> {
-        self.draw_position(context, self.pos)
+    //radius around the center that holds the whole hit box, whatever the rotation
+    pub fn bounding_radius(&self) -> f64 {
+        let dimm: Vector2<f64> = self.object.dimentions() / 2.0;
+        self.object
+            .hit_box
+            .column_iter()
+            .map(|x| (x * self.object.scale - dimm).norm())
+            .fold(0.0, f64::max)
+    }
+
+    //bounding circle used by the broadphase, it covers the movement of the last step
+    pub fn bounds(&self) -> (Point2<f64>, f64) {
+        let path = self.pos - self.prev_pos;
+        (
+            self.get_pos_center() - path / 2.0,
+            self.bounding_radius() + path.norm() / 2.0,
+        )
     }
 
-    pub fn draw_position(
-        &self,
-        context: &WebGlRenderingContext,
-        pos: Point2<f64>,
-    ) -> Result<(), JsValue> {
-        self.object.draw(context, pos, self.rotation, self.color)
+    pub fn snapshot(&mut self) {
+        self.prev_pos = self.pos;
+        self.prev_rotation = self.rotation;
+    }
+
+    pub fn interpolated(&self, alpha: f64) -> (Point2<f64>, f64) {
+        let pos = self.prev_pos + (self.pos - self.prev_pos) * alpha;
+
+        //turn through the shortest way
+        let mut diff = (self.rotation - self.prev_rotation) % TAU;
+        if diff > PI {
+            diff -= TAU;
+        } else if diff < -PI {
+            diff += TAU;
+        }
+
+        (pos, self.prev_rotation + diff * alpha)
     }
 
     fn get_min_max_from_proj(points: Matrix2x3<f64>, axis_proj: Vector2<f64>) -> (f64, f64) {
@@ -71,7 +98,7 @@ impl EntityDrawable {
             min_r1 = q.min(min_r1);
             max_r1 = q.max(max_r1);
         }
-        return (min_r1, max_r1);
+        (min_r1, max_r1)
     }
 
     fn triagle_hit_point(t1: Matrix2x3<f64>, p: Vector2<f64>) -> bool {
@@ -81,7 +108,7 @@ impl EntityDrawable {
             let axis_proj: Vector2<f64> = t1.column(b) - t1.column(a);
             let axis_proj: Vector2<f64> = Vector2::new(-axis_proj.y, axis_proj.x).normalize();
 
-            let (min_r1, max_r1) = EntityDrawable::get_min_max_from_proj(t1, axis_proj);
+            let (min_r1, max_r1) = Entity::get_min_max_from_proj(t1, axis_proj);
             let r2 = p.dotc(&axis_proj);
 
             if !(r2 >= min_r1 && max_r1 >= r2) {
@@ -98,8 +125,8 @@ impl EntityDrawable {
             let axis_proj: Vector2<f64> = t1.column(b) - t1.column(a);
             let axis_proj: Vector2<f64> = Vector2::new(-axis_proj.y, axis_proj.x).normalize();
 
-            let (min_r1, max_r1) = EntityDrawable::get_min_max_from_proj(t1, axis_proj);
-            let (min_r2, max_r2) = EntityDrawable::get_min_max_from_proj(t2, axis_proj);
+            let (min_r1, max_r1) = Entity::get_min_max_from_proj(t1, axis_proj);
+            let (min_r2, max_r2) = Entity::get_min_max_from_proj(t2, axis_proj);
 
             if !(max_r2 >= min_r1 && max_r1 >= min_r2) {
                 return false;
@@ -111,8 +138,8 @@ impl EntityDrawable {
             let axis_proj: Vector2<f64> = t2.column(b) - t2.column(a);
             let axis_proj: Vector2<f64> = Vector2::new(-axis_proj.y, axis_proj.x).normalize();
 
-            let (min_r1, max_r1) = EntityDrawable::get_min_max_from_proj(t1, axis_proj);
-            let (min_r2, max_r2) = EntityDrawable::get_min_max_from_proj(t2, axis_proj);
+            let (min_r1, max_r1) = Entity::get_min_max_from_proj(t1, axis_proj);
+            let (min_r2, max_r2) = Entity::get_min_max_from_proj(t2, axis_proj);
 
             if !(max_r2 >= min_r1 && max_r1 >= min_r2) {
                 return false;
@@ -122,10 +149,10 @@ impl EntityDrawable {
         true
     }
 
-    pub fn transform_triagle(obj: &EntityDrawable, t: Matrix2x3<f64>) -> Matrix2x3<f64> {
+    pub fn transform_triagle(obj: &Entity, t: Matrix2x3<f64>) -> Matrix2x3<f64> {
         let dimm: Vector2<f64> = obj.object.dimentions() / 2.0;
 
-        let rot: Rotation2<f64> = Rotation2::new(-obj.rotation);
+        let rot = trig::rotation(-obj.rotation);
 
         let t: Vec<Vector2<f64>> = t
             .column_iter()
@@ -144,10 +171,22 @@ impl EntityDrawable {
         Matrix2x3::from_columns(&t)
     }
 
-    pub fn transform_all(obj: &EntityDrawable) -> Matrix2xX<f64> {
+    //drawn outline of the entity in world coordinates
+    pub fn outline(&self) -> Vec<Point2<f64>> {
+        let dimm: Vector2<f64> = self.object.dimentions() / 2.0;
+        let rot = trig::rotation(-self.rotation);
+
+        self.object
+            .lst_vec_point
+            .iter()
+            .map(|x| self.pos + dimm + rot * (x.coords * self.object.scale - dimm))
+            .collect()
+    }
+
+    pub fn transform_all(obj: &Entity) -> Matrix2xX<f64> {
         let dimm: Vector2<f64> = obj.object.dimentions() / 2.0;
 
-        let rot: Rotation2<f64> = Rotation2::new(-obj.rotation);
+        let rot = trig::rotation(-obj.rotation);
 
         let t: Vec<Vector2<f64>> = obj
             .object
@@ -183,16 +222,16 @@ impl EntityDrawable {
             max_r1 = q.max(max_r1);
         }
 
-        return (min_r1, max_r1);
+        (min_r1, max_r1)
     }
 
     fn obj_hit(
-        obj1: (&ObjectDrawable, &Vec<usize>, &Matrix2xX<f64>),
-        obj2: (&ObjectDrawable, &Vec<usize>, &Matrix2xX<f64>),
+        obj1: (&Object, &Vec<usize>, &Matrix2xX<f64>),
+        obj2: (&Object, &Vec<usize>, &Matrix2xX<f64>),
     ) -> bool {
         let points1 = obj1
             .1
-            .into_iter()
+            .iter()
             .flat_map(|x| {
                 let p = obj1.0.hit_box_edge.column(*x);
                 [p.x, p.y]
@@ -201,7 +240,7 @@ impl EntityDrawable {
 
         let points2 = obj2
             .1
-            .into_iter()
+            .iter()
             .flat_map(|x| {
                 let p = obj2.0.hit_box_edge.column(*x);
                 [p.x, p.y]
@@ -216,10 +255,8 @@ impl EntityDrawable {
             let axis_proj: Vector2<f64> = edge_end - edge_init;
             let axis_proj: Vector2<f64> = Vector2::new(-axis_proj.y, axis_proj.x).normalize();
 
-            let (min_r1, max_r1) =
-                EntityDrawable::get_min_max_from_proj2(axis_proj, &points1, obj1.2);
-            let (min_r2, max_r2) =
-                EntityDrawable::get_min_max_from_proj2(axis_proj, &points2, obj2.2);
+            let (min_r1, max_r1) = Entity::get_min_max_from_proj2(axis_proj, &points1, obj1.2);
+            let (min_r2, max_r2) = Entity::get_min_max_from_proj2(axis_proj, &points2, obj2.2);
 
             if !(max_r2 >= min_r1 && max_r1 >= min_r2) {
                 return false;
@@ -234,10 +271,8 @@ impl EntityDrawable {
             let axis_proj: Vector2<f64> = edge_end - edge_init;
             let axis_proj: Vector2<f64> = Vector2::new(-axis_proj.y, axis_proj.x).normalize();
 
-            let (min_r1, max_r1) =
-                EntityDrawable::get_min_max_from_proj2(axis_proj, &points1, obj1.2);
-            let (min_r2, max_r2) =
-                EntityDrawable::get_min_max_from_proj2(axis_proj, &points2, obj2.2);
+            let (min_r1, max_r1) = Entity::get_min_max_from_proj2(axis_proj, &points1, obj1.2);
+            let (min_r2, max_r2) = Entity::get_min_max_from_proj2(axis_proj, &points2, obj2.2);
 
             if !(max_r2 >= min_r1 && max_r1 >= min_r2) {
                 return false;
@@ -247,32 +282,43 @@ impl EntityDrawable {
         true
     }
 
-    fn obj_hit_point(
-        obj1: (&ObjectDrawable, &Vec<usize>, &Matrix2xX<f64>),
-        obj2: &Vector2<f64>,
+    //separating axis test between a convex part and the segment a-b
+    //the polygon edges plus the normal of the segment are the only axes to check
+    fn obj_hit_segment(
+        obj1: (&Object, &Vec<usize>, &Matrix2xX<f64>),
+        a: &Vector2<f64>,
+        b: &Vector2<f64>,
     ) -> bool {
         let points1 = obj1
             .1
-            .into_iter()
+            .iter()
             .flat_map(|x| {
                 let p = obj1.0.hit_box_edge.column(*x);
                 [p.x, p.y]
             })
             .collect::<HashSet<usize>>();
 
-        for edge in obj1.1 {
-            let edge = obj1.0.hit_box_edge.column(*edge);
-            let edge_init = obj1.2.column(edge.y);
-            let edge_end = obj1.2.column(edge.x);
+        let mut axes: Vec<Vector2<f64>> = obj1
+            .1
+            .iter()
+            .map(|edge| {
+                let edge = obj1.0.hit_box_edge.column(*edge);
+                let axis_proj: Vector2<f64> = obj1.2.column(edge.x) - obj1.2.column(edge.y);
+                Vector2::new(-axis_proj.y, axis_proj.x).normalize()
+            })
+            .collect();
 
-            let axis_proj: Vector2<f64> = edge_end - edge_init;
-            let axis_proj: Vector2<f64> = Vector2::new(-axis_proj.y, axis_proj.x).normalize();
+        //a bullet that did not move is just a point
+        let path = b - a;
+        if path.norm_squared() > 0.0 {
+            axes.push(Vector2::new(-path.y, path.x).normalize());
+        }
 
-            let (min_r1, max_r1) =
-                EntityDrawable::get_min_max_from_proj2(axis_proj, &points1, obj1.2);
-            let r2 = obj2.dotc(&axis_proj);
+        for axis_proj in axes {
+            let (min_r1, max_r1) = Entity::get_min_max_from_proj2(axis_proj, &points1, obj1.2);
+            let (ra, rb) = (a.dotc(&axis_proj), b.dotc(&axis_proj));
 
-            if !(r2 >= min_r1 && max_r1 >= r2) {
+            if !(ra.max(rb) >= min_r1 && max_r1 >= ra.min(rb)) {
                 return false;
             }
         }
@@ -280,20 +326,38 @@ impl EntityDrawable {
         true
     }
 
-    pub fn hit2(&self, other: &EntityDrawable) -> bool {
-        let p1: Matrix2xX<f64> = EntityDrawable::transform_all(self);
-        let p2: Matrix2xX<f64> = EntityDrawable::transform_all(other);
+    pub fn hit2(&self, other: &Entity) -> bool {
+        self.hit_moved(other, Vector2::zeros())
+    }
+
+    //on a map that wraps around, an entity straddling an edge is hit from either side
+    pub fn hit_wrapped(&self, other: &Entity, map_dim: Vector2<f64>) -> bool {
+        let offset = wrap_offset(self.get_pos_center(), other.get_pos_center(), map_dim);
+        self.hit_moved(other, offset)
+    }
+
+    //other is tested as if it were moved by offset
+    fn hit_moved(&self, other: &Entity, offset: Vector2<f64>) -> bool {
+        let p1: Matrix2xX<f64> = Entity::transform_all(self);
+        let mut p2: Matrix2xX<f64> = Entity::transform_all(other);
+        for mut point in p2.column_iter_mut() {
+            point += offset;
+        }
 
         for obj1 in self.object.hit_box_obj.iter() {
             for obj2 in other.object.hit_box_obj.iter() {
-                if EntityDrawable::obj_hit((&self.object, obj1, &p1), (&other.object, obj2, &p2)) {
+                if Entity::obj_hit((&self.object, obj1, &p1), (&other.object, obj2, &p2)) {
                     return true;
                 }
             }
 
-            if other.object.hit_box_obj.len() == 0 {
-                let p: Vector2<f64> = other.pos.coords + other.object.dimentions() / 2.0;
-                if EntityDrawable::obj_hit_point((&self.object, obj1, &p1), &p) {
+            //point-like entities (bullets) are tested along the whole path of the step,
+            //seen from self so its own movement counts too
+            if other.object.hit_box_obj.is_empty() {
+                let end: Vector2<f64> = other.get_pos_center().coords + offset;
+                let start: Vector2<f64> =
+                    end - (other.pos - other.prev_pos) + (self.pos - self.prev_pos);
+                if Entity::obj_hit_segment((&self.object, obj1, &p1), &start, &end) {
                     return true;
                 }
             }
@@ -305,7 +369,7 @@ impl EntityDrawable {
     /**
      * check if two entity hit
      */
-    pub fn hit(&self, other: &EntityDrawable) -> bool {
+    pub fn hit(&self, other: &Entity) -> bool {
         //order by number of triagles
         //bigger always first
         let (first, other) = {
@@ -322,12 +386,12 @@ impl EntityDrawable {
         );
 
         for triagle in lst1.clone() {
-            let triagle = EntityDrawable::transform_triagle(first, *triagle);
+            let triagle = Entity::transform_triagle(first, *triagle);
             for other_triagle in lst2.clone() {
-                let other_triagle = EntityDrawable::transform_triagle(other, *other_triagle);
+                let other_triagle = Entity::transform_triagle(other, *other_triagle);
 
                 //if one hit box hits the other all the entity hit
-                if EntityDrawable::triagle_hit(triagle, other_triagle) {
+                if Entity::triagle_hit(triagle, other_triagle) {
                     return true;
                 }
             }
@@ -337,7 +401,7 @@ impl EntityDrawable {
                 let p = other.pos.coords + other.object.dimentions() / 2.0;
 
                 //if one hit box hits the other all the entity hit
-                if EntityDrawable::triagle_hit_point(triagle, p) {
+                if Entity::triagle_hit_point(triagle, p) {
                     return true;
                 }
             }
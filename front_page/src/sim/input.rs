@@ -0,0 +1,121 @@
+use std::ops::{Index, IndexMut};
+
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ButtonState {
+    #[default]
+    None,
+    Pressed,
+    Hold,
+    Released,
+}
+
+impl ButtonState {
+    //the only place the state changes from one frame to the next
+    //`down`: held right now, `tapped`: went down since the last frame (even if already up again)
+    pub fn next(self, down: bool, tapped: bool) -> ButtonState {
+        match (down, tapped, self) {
+            (_, true, _) => ButtonState::Pressed,
+            (true, false, ButtonState::Pressed | ButtonState::Hold) => ButtonState::Hold,
+            (true, false, ButtonState::None | ButtonState::Released) => ButtonState::Pressed,
+            (false, false, ButtonState::Pressed | ButtonState::Hold) => ButtonState::Released,
+            (false, false, ButtonState::None | ButtonState::Released) => ButtonState::None,
+        }
+    }
+
+    pub fn is_down(&self) -> bool {
+        matches!(self, ButtonState::Pressed | ButtonState::Hold)
+    }
+
+    //returns true only once per press
+    pub(crate) fn take_pressed(&mut self) -> bool {
+        if let ButtonState::Pressed = self {
+            *self = ButtonState::Hold;
+            return true;
+        }
+        false
+    }
+}
+
+//everything the player can ask the ship (or the game) to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Thrust,
+    Fire,
+    RotateLeft,
+    RotateRight,
+    Hyperspace,
+    //energy shield, up while held
+    Shield,
+    Pause,
+    Start,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::Thrust,
+        Action::Fire,
+        Action::RotateLeft,
+        Action::RotateRight,
+        Action::Hyperspace,
+        Action::Shield,
+        Action::Pause,
+        Action::Start,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Thrust => "Thrust",
+            Action::Fire => "Fire",
+            Action::RotateLeft => "Rotate left",
+            Action::RotateRight => "Rotate right",
+            Action::Hyperspace => "Hyperspace",
+            Action::Shield => "Shield",
+            Action::Pause => "Pause",
+            Action::Start => "Start",
+        }
+    }
+}
+
+//state of every action
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Actions([ButtonState; Action::ALL.len()]);
+
+impl Index<Action> for Actions {
+    type Output = ButtonState;
+    fn index(&self, action: Action) -> &ButtonState {
+        &self.0[action as usize]
+    }
+}
+
+impl IndexMut<Action> for Actions {
+    fn index_mut(&mut self, action: Action) -> &mut ButtonState {
+        &mut self.0[action as usize]
+    }
+}
+
+//how the ship is steered: towards the mouse, or rotate and thrust like the arcade
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlScheme {
+    #[default]
+    Mouse,
+    Keyboard,
+}
+
+//everything the player can do during one simulation step, this is what replays record
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserInput {
+    //where the mouse points, in world coordinates, the mouse scheme steers towards it
+    pub pointer: Point2<f64>,
+    pub actions: Actions,
+    pub scheme: ControlScheme,
+}
+
+impl UserInput {
+    //the pointer is kept in whole world units, so replays store it as small integers
+    pub fn quantize(&mut self) {
+        self.pointer = self.pointer.map(f64::round);
+    }
+}
@@ -0,0 +1,28 @@
+mod broadphase;
+mod controls;
+mod data;
+mod entity;
+mod input;
+mod object;
+mod particle;
+mod powerup;
+mod replay;
+mod rng;
+mod saucer;
+pub mod trig;
+mod weapon;
+mod world;
+
+pub use broadphase::*;
+pub use controls::*;
+pub use data::*;
+pub use entity::*;
+pub use input::*;
+pub use object::*;
+pub use particle::*;
+pub use powerup::*;
+pub use replay::*;
+pub use rng::*;
+pub use saucer::*;
+pub use weapon::*;
+pub use world::*;
@@ -0,0 +1,53 @@
+use nalgebra::{Matrix2x3, Matrix2xX, Point2, Vector2};
+use serde::Deserialize;
+
+use super::data::Model;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DrawMode {
+    #[default]
+    LineLoop,
+    TriangleFan,
+    TriangleStrip,
+    Lines,
+    Triangles,
+    Points,
+}
+
+//shape of an entity as described by the json files in `data`
+//only the geometry lives here, the gpu side is built from it by the renderer
+#[derive(Deserialize, Debug, Clone)]
+pub struct Object {
+    #[serde(skip, default)]
+    pub model: Model,
+    pub dimentions: Vector2<f64>,
+    pub hit_box: Matrix2xX<f64>,
+    pub hit_box_edge: Matrix2xX<usize>,
+    pub hit_box_obj: Vec<Vec<usize>>,
+    pub lst_vec_point: Vec<Point2<f64>>,
+    pub lst_hit_box: Vec<Matrix2x3<f64>>,
+    #[serde(default)]
+    pub scale: f64,
+    #[serde(default)]
+    pub draw_mode: DrawMode,
+}
+
+impl Object {
+    pub fn load(model: Model) -> Object {
+        let mut object: Object = model.json().try_into().unwrap();
+        object.model = model;
+        object
+    }
+
+    pub fn dimentions(&self) -> Vector2<f64> {
+        self.dimentions * self.scale
+    }
+}
+
+impl TryFrom<&str> for Object {
+    type Error = serde_json::Error;
+    fn try_from(data: &str) -> Result<Self, Self::Error> {
+        let ship: Object = serde_json::from_str(data)?;
+        Ok(ship)
+    }
+}
@@ -0,0 +1,164 @@
+use nalgebra::{Point2, Vector2, Vector3};
+
+use super::{object::DrawMode, trig};
+
+//most particles alive at once, new ones are dropped while the pool is full
+pub const MAX_PARTICLES: usize = 1024;
+
+//something blew up, for the renderer to shake the screen or play a sound
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Explosion {
+    pub pos: Point2<f64>,
+    //0.0 for nothing up to 1.0 for the biggest one
+    pub strength: f64,
+}
+
+//short lived visual effect, it never interacts with the game
+#[derive(Debug, Clone)]
+pub struct Particle {
+    pub pos: Point2<f64>,
+    pub prev_pos: Point2<f64>,
+    pub speed: Vector2<f64>,
+    //time in ms left and total lifetime, the particle fades out between them
+    pub life: f64,
+    pub ttl: f64,
+    pub color: Vector3<f64>,
+    //Points or Lines, a line is a segment of `length` centered on `pos`
+    pub draw_mode: DrawMode,
+    pub length: f64,
+    pub rotation: f64,
+    pub prev_rotation: f64,
+    pub spin: f64,
+}
+
+impl Particle {
+    pub fn point(pos: Point2<f64>, speed: Vector2<f64>, ttl: f64, color: Vector3<f64>) -> Self {
+        Particle {
+            pos,
+            prev_pos: pos,
+            speed,
+            life: ttl,
+            ttl,
+            color,
+            draw_mode: DrawMode::Points,
+            length: 0.0,
+            rotation: 0.0,
+            prev_rotation: 0.0,
+            spin: 0.0,
+        }
+    }
+
+    //segment from a to b
+    pub fn line(
+        a: Point2<f64>,
+        b: Point2<f64>,
+        speed: Vector2<f64>,
+        spin: f64,
+        ttl: f64,
+        color: Vector3<f64>,
+    ) -> Self {
+        let dir = b - a;
+        let rotation = trig::atan2(dir.y, dir.x);
+        Particle {
+            draw_mode: DrawMode::Lines,
+            length: dir.norm(),
+            rotation,
+            prev_rotation: rotation,
+            spin,
+            ..Particle::point(a + dir / 2.0, speed, ttl, color)
+        }
+    }
+
+    //1.0 when just emitted down to 0.0 when gone
+    pub fn fade(&self) -> f64 {
+        (self.life / self.ttl).clamp(0.0, 1.0)
+    }
+
+    //ends of the particle at the given interpolation alpha, both equal for points
+    pub fn ends(&self, alpha: f64) -> (Point2<f64>, Point2<f64>) {
+        let pos = self.prev_pos + (self.pos - self.prev_pos) * alpha;
+        let rotation = self.prev_rotation + (self.rotation - self.prev_rotation) * alpha;
+        let half = trig::rotation(rotation) * Vector2::new(self.length / 2.0, 0.0);
+        (pos - half, pos + half)
+    }
+}
+
+//fixed size pool, the alive particles are kept at the front so nothing is allocated while playing
+#[derive(Debug, Clone)]
+pub struct ParticlePool {
+    particles: Vec<Particle>,
+    alive: usize,
+}
+
+impl Default for ParticlePool {
+    fn default() -> Self {
+        let dead = Particle::point(Point2::origin(), Vector2::zeros(), 0.0, Vector3::zeros());
+        ParticlePool {
+            particles: vec![dead; MAX_PARTICLES],
+            alive: 0,
+        }
+    }
+}
+
+impl ParticlePool {
+    pub fn emit(&mut self, particle: Particle) {
+        if self.alive < self.particles.len() {
+            self.particles[self.alive] = particle;
+            self.alive += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.alive = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.alive
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alive == 0
+    }
+
+    //the alive particles
+    pub fn as_slice(&self) -> &[Particle] {
+        &self.particles[..self.alive]
+    }
+
+    //nothing moves, so the drawing must not interpolate either
+    pub fn freeze(&mut self) {
+        for particle in self.particles[..self.alive].iter_mut() {
+            particle.prev_pos = particle.pos;
+            particle.prev_rotation = particle.rotation;
+        }
+    }
+
+    //moves the particles and wraps them around the map like the entities
+    pub fn update(&mut self, delta: f64, map_dim: Vector2<f64>) {
+        let half = map_dim / 2.0;
+        let mut i = 0;
+        while i < self.alive {
+            let particle = &mut self.particles[i];
+            particle.life -= delta;
+            if particle.life <= 0.0 {
+                self.alive -= 1;
+                self.particles.swap(i, self.alive);
+                continue;
+            }
+
+            particle.prev_pos = particle.pos;
+            particle.prev_rotation = particle.rotation;
+            particle.pos += particle.speed * delta;
+            particle.rotation += particle.spin * delta;
+
+            let old = particle.pos;
+            for axis in 0..2 {
+                if particle.pos[axis].abs() > half[axis] {
+                    particle.pos[axis] -= particle.pos[axis].signum() * map_dim[axis];
+                }
+            }
+            particle.prev_pos += particle.pos - old;
+            i += 1;
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use nalgebra::Vector3;
+
+//what a pickup grants once the ship touches it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUpKind {
+    //absorbs the next hit
+    Shield,
+    //three bullets per shot
+    Spread,
+    //shorter fire cooldown
+    RapidFire,
+    ExtraLife,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 4] = [
+        PowerUpKind::Shield,
+        PowerUpKind::Spread,
+        PowerUpKind::RapidFire,
+        PowerUpKind::ExtraLife,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "shield",
+            PowerUpKind::Spread => "spread",
+            PowerUpKind::RapidFire => "rapid fire",
+            PowerUpKind::ExtraLife => "extra life",
+        }
+    }
+
+    //time in ms the effect lasts, None for the ones applied at once
+    pub fn duration(&self) -> Option<f64> {
+        match self {
+            PowerUpKind::Shield => Some(15000.0),
+            PowerUpKind::Spread => Some(10000.0),
+            PowerUpKind::RapidFire => Some(10000.0),
+            PowerUpKind::ExtraLife => None,
+        }
+    }
+
+    pub fn color(&self) -> Vector3<f64> {
+        match self {
+            PowerUpKind::Shield => Vector3::new(0.0, 0.4, 1.0),
+            PowerUpKind::Spread => Vector3::new(1.0, 0.5, 0.0),
+            PowerUpKind::RapidFire => Vector3::new(0.9, 0.0, 0.0),
+            PowerUpKind::ExtraLife => Vector3::new(0.0, 0.7, 0.0),
+        }
+    }
+}
+
+//a timed effect the ship currently has
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Effect {
+    pub kind: PowerUpKind,
+    //time in ms left
+    pub time_left: f64,
+}
@@ -0,0 +1,312 @@
+use std::fmt;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use super::input::{Action, ButtonState, ControlScheme, UserInput};
+
+//replays only play back on the simulation that recorded them
+//bump this with every change that makes a run play differently (physics, spawns, rng use...)
+pub const VERSION: u32 = 3;
+
+//the seed plus the input of every simulation step of a run, enough to rebuild it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    //run-length encoded: (how many steps in a row, input of those steps)
+    //saved packed into a string, see `packed`
+    #[serde(with = "packed")]
+    frames: Vec<(u32, UserInput)>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Json(serde_json::Error),
+    Version(u32),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Json(err) => write!(f, "invalid replay: {}", err),
+            ReplayError::Version(version) => write!(
+                f,
+                "replay recorded on version {}, this is {}",
+                version, VERSION
+            ),
+        }
+    }
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Replay {
+            version: VERSION,
+            seed,
+            frames: vec![],
+        }
+    }
+
+    pub fn push(&mut self, input: &UserInput) {
+        let mut input = input.clone();
+        input.quantize();
+        match self.frames.last_mut() {
+            Some((count, last)) if *last == input => *count += 1,
+            _ => self.frames.push((1, input)),
+        }
+    }
+
+    //number of simulation steps recorded
+    pub fn len(&self) -> usize {
+        self.frames.iter().map(|(count, _)| *count as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Replay, ReplayError> {
+        let replay: Replay = serde_json::from_str(json).map_err(ReplayError::Json)?;
+        if replay.version != VERSION {
+            return Err(ReplayError::Version(replay.version));
+        }
+        Ok(replay)
+    }
+}
+
+//position of the playback inside a replay
+//the replay itself is shared, the checkpoints of `World::seek` only copy the position
+#[derive(Debug, Clone)]
+pub struct Playback {
+    replay: Rc<Replay>,
+    frame: usize,
+    repeat: u32,
+    tick: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Rc<Replay>) -> Self {
+        Playback {
+            replay,
+            frame: 0,
+            repeat: 0,
+            tick: 0,
+        }
+    }
+
+    pub fn replay(&self) -> &Rc<Replay> {
+        &self.replay
+    }
+
+    pub fn tick(&self) -> usize {
+        self.tick
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.replay.frames.len()
+    }
+
+    //input of the next step, None once the replay is over
+    pub fn next_input(&mut self) -> Option<&UserInput> {
+        let (count, input) = self.replay.frames.get(self.frame)?;
+
+        self.repeat += 1;
+        self.tick += 1;
+        if self.repeat >= *count {
+            self.frame += 1;
+            self.repeat = 0;
+        }
+        Some(input)
+    }
+}
+
+//the frames as base64 bytes, a few bytes per run of steps instead of a json object:
+//  count (varint), what changed (bit 0 actions, bit 1 scheme, bit 2 pointer),
+//  then only what changed since the previous frame:
+//  actions (2 bits each, u16 le), scheme (u8), pointer (zigzag varint deltas of x and y)
+mod packed {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use super::*;
+
+    const ACTIONS: u8 = 1;
+    const SCHEME: u8 = 2;
+    const POINTER: u8 = 4;
+
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    pub fn serialize<S: Serializer>(
+        frames: &[(u32, UserInput)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_base64(&pack(frames)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(u32, UserInput)>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let bytes = from_base64(&text).ok_or_else(|| de::Error::custom("invalid base64"))?;
+        unpack(&bytes).ok_or_else(|| de::Error::custom("invalid frames"))
+    }
+
+    fn pack(frames: &[(u32, UserInput)]) -> Vec<u8> {
+        let mut out = vec![];
+        let mut last = UserInput::default();
+        for (count, input) in frames {
+            write_varint(&mut out, *count as u64);
+
+            let actions = action_bits(input);
+            let mut changed = 0;
+            if actions != action_bits(&last) {
+                changed |= ACTIONS;
+            }
+            if input.scheme != last.scheme {
+                changed |= SCHEME;
+            }
+            if input.pointer != last.pointer {
+                changed |= POINTER;
+            }
+            out.push(changed);
+
+            if changed & ACTIONS != 0 {
+                out.extend(actions.to_le_bytes());
+            }
+            if changed & SCHEME != 0 {
+                out.push(input.scheme as u8);
+            }
+            if changed & POINTER != 0 {
+                let delta = input.pointer - last.pointer;
+                write_varint(&mut out, zigzag(delta.x as i64));
+                write_varint(&mut out, zigzag(delta.y as i64));
+            }
+            last = input.clone();
+        }
+        out
+    }
+
+    fn unpack(bytes: &[u8]) -> Option<Vec<(u32, UserInput)>> {
+        let mut frames = vec![];
+        let mut last = UserInput::default();
+        let mut bytes = bytes.iter().copied();
+        while let Some(first) = bytes.next() {
+            let count = u32::try_from(read_varint(first, &mut bytes)?).ok()?;
+            let changed = bytes.next()?;
+
+            let mut input = last.clone();
+            if changed & ACTIONS != 0 {
+                let bits = u16::from_le_bytes([bytes.next()?, bytes.next()?]);
+                for (i, action) in Action::ALL.into_iter().enumerate() {
+                    input.actions[action] = match (bits >> (2 * i)) & 3 {
+                        0 => ButtonState::None,
+                        1 => ButtonState::Pressed,
+                        2 => ButtonState::Hold,
+                        _ => ButtonState::Released,
+                    };
+                }
+            }
+            if changed & SCHEME != 0 {
+                input.scheme = match bytes.next()? {
+                    0 => ControlScheme::Mouse,
+                    1 => ControlScheme::Keyboard,
+                    _ => return None,
+                };
+            }
+            if changed & POINTER != 0 {
+                let x = unzigzag(read_varint(bytes.next()?, &mut bytes)?);
+                let y = unzigzag(read_varint(bytes.next()?, &mut bytes)?);
+                input.pointer.x += x as f64;
+                input.pointer.y += y as f64;
+            }
+
+            frames.push((count, input.clone()));
+            last = input;
+        }
+        Some(frames)
+    }
+
+    fn action_bits(input: &UserInput) -> u16 {
+        Action::ALL
+            .into_iter()
+            .enumerate()
+            .map(|(i, action)| (input.actions[action] as u16) << (2 * i))
+            .fold(0, |bits, x| bits | x)
+    }
+
+    fn zigzag(x: i64) -> u64 {
+        ((x << 1) ^ (x >> 63)) as u64
+    }
+
+    fn unzigzag(x: u64) -> i64 {
+        (x >> 1) as i64 ^ -((x & 1) as i64)
+    }
+
+    //7 bits per byte, the high bit says another byte follows
+    fn write_varint(out: &mut Vec<u8>, mut x: u64) {
+        while x >= 0x80 {
+            out.push(x as u8 | 0x80);
+            x >>= 7;
+        }
+        out.push(x as u8);
+    }
+
+    fn read_varint(first: u8, bytes: &mut impl Iterator<Item = u8>) -> Option<u64> {
+        let mut x = 0;
+        let mut byte = first;
+        for shift in (0..64).step_by(7) {
+            x |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(x);
+            }
+            byte = bytes.next()?;
+        }
+        None
+    }
+
+    fn to_base64(bytes: &[u8]) -> String {
+        let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let b = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    fn from_base64(text: &str) -> Option<Vec<u8>> {
+        let text = text.as_bytes();
+        if !text.len().is_multiple_of(4) {
+            return None;
+        }
+        let mut out = Vec::with_capacity(text.len() / 4 * 3);
+        for chunk in text.chunks(4) {
+            let padding = chunk.iter().rev().take_while(|&&x| x == b'=').count();
+            if padding > 2 {
+                return None;
+            }
+            let mut n = 0;
+            for &c in &chunk[..4 - padding] {
+                let value = ALPHABET.iter().position(|&x| x == c)? as u32;
+                n = n << 6 | value;
+            }
+            n <<= 6 * padding;
+            out.extend(&n.to_be_bytes()[1..4 - padding]);
+        }
+        Some(out)
+    }
+}
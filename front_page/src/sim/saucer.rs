@@ -0,0 +1,83 @@
+use nalgebra::{Point2, Vector2};
+
+//the large saucer shoots anywhere, the small one aims at the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaucerSize {
+    Large,
+    Small,
+}
+
+impl SaucerSize {
+    pub fn points(&self) -> u32 {
+        match self {
+            SaucerSize::Large => 200,
+            SaucerSize::Small => 1000,
+        }
+    }
+
+    pub fn scale(&self) -> f64 {
+        match self {
+            SaucerSize::Large => 2.0,
+            SaucerSize::Small => 1.0,
+        }
+    }
+
+    //horizontal units per ms
+    pub fn speed(&self) -> f64 {
+        match self {
+            SaucerSize::Large => 0.2,
+            SaucerSize::Small => 0.3,
+        }
+    }
+
+    //time in ms between two shots
+    pub fn fire_time(&self) -> f64 {
+        match self {
+            SaucerSize::Large => 1000.0,
+            SaucerSize::Small => 800.0,
+        }
+    }
+}
+
+//direction to shoot from `from` so a bullet of `bullet_speed` meets a target moving
+//at a constant speed, falls back to aiming straight at it when it can't be reached
+pub fn lead_direction(
+    from: Point2<f64>,
+    target: Point2<f64>,
+    target_speed: Vector2<f64>,
+    bullet_speed: f64,
+) -> Vector2<f64> {
+    //|d + v*t| = bullet_speed * t, solved for the time t of the meeting
+    let d = target - from;
+    let a = target_speed.norm_squared() - bullet_speed * bullet_speed;
+    let b = 2.0 * d.dot(&target_speed);
+    let c = d.norm_squared();
+
+    let time = if a.abs() < 1e-9 {
+        -c / b
+    } else {
+        let delta = b * b - 4.0 * a * c;
+        if delta < 0.0 {
+            -1.0
+        } else {
+            let t1 = (-b - delta.sqrt()) / (2.0 * a);
+            let t2 = (-b + delta.sqrt()) / (2.0 * a);
+            match (t1 > 0.0, t2 > 0.0) {
+                (true, true) => t1.min(t2),
+                (true, false) => t1,
+                (false, true) => t2,
+                (false, false) => -1.0,
+            }
+        }
+    };
+
+    let aim = if time > 0.0 {
+        d + target_speed * time
+    } else {
+        d
+    };
+    aim.try_normalize(1e-9)
+        .or_else(|| d.try_normalize(1e-9))
+        //right on top of the target, any direction will do
+        .unwrap_or_else(Vector2::x)
+}
@@ -0,0 +1,105 @@
+//sin, cos and friends made only of + - * / and sqrt, which ieee 754 rounds the same way
+//everywhere, so a run gives the same bits on wasm and native
+//the std versions call the platform libm and can differ in the last bit between targets
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_6, PI};
+
+use nalgebra::{Matrix2, Rotation2};
+
+//taylor series on [-pi/4, pi/4], the first term left out is below 2e-16
+fn sin_kernel(x: f64) -> f64 {
+    let x2 = x * x;
+    let mut term = x;
+    let mut sum = x;
+    for n in (2..=16).step_by(2) {
+        term *= -x2 / (n * (n + 1)) as f64;
+        sum += term;
+    }
+    sum
+}
+
+fn cos_kernel(x: f64) -> f64 {
+    let x2 = x * x;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for n in (1..=16).step_by(2) {
+        term *= -x2 / (n * (n + 1)) as f64;
+        sum += term;
+    }
+    sum
+}
+
+pub fn sin_cos(x: f64) -> (f64, f64) {
+    //quarter turns, the rest is in [-pi/4, pi/4]
+    let quadrant = (x / FRAC_PI_2).round();
+    let r = x - quadrant * FRAC_PI_2;
+    let (s, c) = (sin_kernel(r), cos_kernel(r));
+    match (quadrant as i64).rem_euclid(4) {
+        0 => (s, c),
+        1 => (c, -s),
+        2 => (-s, -c),
+        _ => (-c, s),
+    }
+}
+
+pub fn sin(x: f64) -> f64 {
+    sin_cos(x).0
+}
+
+pub fn cos(x: f64) -> f64 {
+    sin_cos(x).1
+}
+
+//taylor series, good enough for |x| <= tan(pi/12)
+fn atan_kernel(x: f64) -> f64 {
+    let x2 = x * x;
+    let mut power = x;
+    let mut sum = x;
+    for n in 1..=12 {
+        power *= -x2;
+        sum += power / (2 * n + 1) as f64;
+    }
+    sum
+}
+
+pub fn atan(x: f64) -> f64 {
+    if x < 0.0 {
+        return -atan(-x);
+    }
+    if x > 1.0 {
+        return FRAC_PI_2 - atan(1.0 / x);
+    }
+    //atan(x) = pi/6 + atan((x*sqrt(3) - 1) / (x + sqrt(3)))
+    let tan_pi_12 = 2.0 - 3f64.sqrt();
+    if x > tan_pi_12 {
+        let sqrt3 = 3f64.sqrt();
+        return FRAC_PI_6 + atan_kernel((x * sqrt3 - 1.0) / (x + sqrt3));
+    }
+    atan_kernel(x)
+}
+
+pub fn atan2(y: f64, x: f64) -> f64 {
+    if x == 0.0 {
+        return match y {
+            y if y > 0.0 => FRAC_PI_2,
+            y if y < 0.0 => -FRAC_PI_2,
+            _ => 0.0,
+        };
+    }
+    let angle = atan(y / x);
+    match (x < 0.0, y < 0.0) {
+        (false, _) => angle,
+        (true, false) => angle + PI,
+        (true, true) => angle - PI,
+    }
+}
+
+pub fn acos(x: f64) -> f64 {
+    let x = x.clamp(-1.0, 1.0);
+    atan2((1.0 - x * x).sqrt(), x)
+}
+
+//same as `Rotation2::new`, which uses the std sin and cos
+pub fn rotation(angle: f64) -> Rotation2<f64> {
+    let (s, c) = sin_cos(angle);
+    Rotation2::from_matrix_unchecked(Matrix2::new(c, -s, s, c))
+}
@@ -0,0 +1,23 @@
+//tuning of the player gun
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeaponConfig {
+    //minimum time in ms between two shots
+    pub cooldown: f64,
+    //time in ms a bullet flies before vanishing, bullets wrap around the map meanwhile
+    pub bullet_ttl: f64,
+    //no new shot while this many bullets are alive
+    pub max_bullets: usize,
+    //units per ms, added to the speed of the ship
+    pub bullet_speed: f64,
+}
+
+impl Default for WeaponConfig {
+    fn default() -> Self {
+        WeaponConfig {
+            cooldown: 150.0,
+            bullet_ttl: 1200.0,
+            max_bullets: 6,
+            bullet_speed: 1.0,
+        }
+    }
+}
@@ -0,0 +1,1408 @@
+use std::f64::consts::TAU;
+use std::rc::Rc;
+
+use nalgebra::{Point2, Vector2, Vector3};
+
+use super::{
+    broadphase::{circles_overlap, wrapped_delta, Grid},
+    data::Model,
+    input::{Action, ControlScheme, UserInput},
+    particle::{Explosion, Particle, ParticlePool},
+    powerup::{Effect, PowerUpKind},
+    replay::{Playback, Replay},
+    rng::Rng,
+    saucer::{lead_direction, SaucerSize},
+    trig,
+    weapon::WeaponConfig,
+    Entity,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Title,
+    Playing,
+    Paused,
+    Dying,
+    GameOver,
+}
+
+//time in ms the player explosion lasts before the game moves on
+pub const DYING_TIME: f64 = 2000.0;
+const START_LIVES: u32 = 3;
+//the ship only respawns when no asteroid is closer than this to the map centre
+const SAFE_RADIUS: f64 = 300.0;
+//time in ms the ship ignores hits after (re)spawning, it blinks meanwhile
+pub const INVULNERABLE_TIME: f64 = 3000.0;
+const BLINK_TIME: f64 = 150.0;
+//asteroids spawn large and split down to small
+const ASTEROID_MAX_SIZE: u32 = 3;
+//kills closer than this (ms) to the previous one raise the multiplier
+pub const COMBO_TIME: f64 = 1000.0;
+pub const MAX_MULTIPLIER: u32 = 4;
+pub const EXTRA_LIFE_SCORE: u32 = 10000;
+//the simulation always advances in steps of this size (ms), 120Hz
+pub const FIXED_STEP: f64 = 1000.0 / 120.0;
+//most steps simulated in a single frame, a long pause (backgrounded tab) is dropped
+pub const MAX_STEPS: u32 = 8;
+//replay steps between two saved states, seeking starts from the closest one (5s)
+pub const CHECKPOINT_STEPS: usize = 600;
+//keyboard scheme: turning speed in radians per ms and thrust in units per ms²
+const ROTATION_SPEED: f64 = 0.005;
+const THRUST: f64 = 0.001;
+//time in ms of play between two saucers
+const SAUCER_TIME: f64 = 15000.0;
+//time in ms between two changes of direction of the zig-zag
+const SAUCER_TURN_TIME: f64 = 1000.0;
+const SAUCER_BULLET_SPEED: f64 = 0.6;
+const SAUCER_BULLET_TTL: f64 = 1500.0;
+//the small saucer misses by up to this many degrees
+const SAUCER_AIM_ERROR: f64 = 5.0;
+const SAUCER_COLOR: Vector3<f64> = Vector3::new(0.0, 0.6, 0.0);
+//chance of a destroyed asteroid leaving a pickup behind
+const POWER_UP_CHANCE: f64 = 0.1;
+//time in ms a pickup floats around before vanishing
+const POWER_UP_TTL: f64 = 8000.0;
+const SPREAD_ANGLE: f64 = 15.0;
+//rapid fire divides the weapon cooldown by this
+const RAPID_FIRE: f64 = 3.0;
+//time in ms the ship ignores hits after the shield absorbed one
+const SHIELD_GRACE: f64 = 1000.0;
+//time in ms between two hyperspace jumps
+const HYPERSPACE_COOLDOWN: f64 = 3000.0;
+//chance of the ship breaking apart in the jump
+const HYPERSPACE_FAIL_CHANCE: f64 = 0.08;
+const HYPERSPACE_COLOR: Vector3<f64> = Vector3::new(0.5, 0.0, 1.0);
+//the energy shield drains in this many ms and takes SHIELD_RECHARGE to fill up again
+const SHIELD_DRAIN: f64 = 3000.0;
+const SHIELD_RECHARGE: f64 = 10000.0;
+//once the energy runs out the shield stays down (and doesn't recharge) for this long
+const SHIELD_COOLDOWN: f64 = 2000.0;
+pub const SHIELD_COLOR: Vector3<f64> = Vector3::new(0.0, 0.8, 0.8);
+//the effects get their own generator so tuning them never changes how a seed plays
+const FX_SEED: u64 = 0x5eed_f0f0_f0f0_f0f0;
+const EXHAUST_COLOR: Vector3<f64> = Vector3::new(1.0, 0.5, 0.0);
+//asteroids and saucers blow up with 0.1 per size
+const SHIP_EXPLOSION: f64 = 0.6;
+//side of a broadphase cell, the map is 10x10 cells
+const GRID_CELL: f64 = 200.0;
+
+fn asteroid_scale(size: u32) -> f64 {
+    2.0 * size as f64
+}
+
+//classic arcade values, the smaller the rock the more it is worth
+fn asteroid_points(size: u32) -> u32 {
+    match size {
+        0 | 1 => 100,
+        2 => 50,
+        _ => 20,
+    }
+}
+
+#[derive(Clone, Debug)]
+enum GameEntity {
+    Asteroid {
+        entity: Entity,
+        size: u32,
+        hit: bool,
+    },
+    Bullet {
+        entity: Entity,
+        hit: bool,
+        //time in ms left before the bullet vanishes
+        ttl: f64,
+        owner: Owner,
+    },
+    Saucer {
+        entity: Entity,
+        size: SaucerSize,
+        hit: bool,
+        //horizontal distance flown, the saucer leaves once it crossed the map
+        travelled: f64,
+        fire_timer: f64,
+        turn_timer: f64,
+    },
+    PowerUp {
+        entity: Entity,
+        kind: PowerUpKind,
+        hit: bool,
+        //time in ms left before the pickup vanishes
+        ttl: f64,
+    },
+}
+
+//who fired a bullet, saucer bullets hit the ship and asteroids but not saucers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Owner {
+    Player,
+    Saucer,
+}
+
+impl GameEntity {
+    fn set_hit(&mut self, hit: bool) {
+        match self {
+            GameEntity::Asteroid { hit: h, .. } => *h = hit,
+            GameEntity::Bullet { hit: h, .. } => *h = hit,
+            GameEntity::Saucer { hit: h, .. } => *h = hit,
+            GameEntity::PowerUp { hit: h, .. } => *h = hit,
+        }
+    }
+    fn is_hit(&self) -> bool {
+        match self {
+            GameEntity::Asteroid { hit, .. } => *hit,
+            GameEntity::Bullet { hit, .. } => *hit,
+            GameEntity::Saucer { hit, .. } => *hit,
+            GameEntity::PowerUp { hit, .. } => *hit,
+        }
+    }
+    fn get_entity(&self) -> &Entity {
+        match self {
+            GameEntity::Asteroid { entity, .. } => entity,
+            GameEntity::Bullet { entity, .. } => entity,
+            GameEntity::Saucer { entity, .. } => entity,
+            GameEntity::PowerUp { entity, .. } => entity,
+        }
+    }
+
+    fn get_entity_mut(&mut self) -> &mut Entity {
+        match self {
+            GameEntity::Asteroid { entity, .. } => entity,
+            GameEntity::Bullet { entity, .. } => entity,
+            GameEntity::Saucer { entity, .. } => entity,
+            GameEntity::PowerUp { entity, .. } => entity,
+        }
+    }
+}
+
+//the whole game simulation, it knows nothing about how (or if) it is drawn
+#[derive(Clone)]
+pub struct World {
+    player: Entity,
+    entities: Vec<GameEntity>,
+    pub map_dim: Vector2<f64>,
+    //bullets by cell, rebuilt every step
+    grid: Grid,
+    pub input: UserInput,
+    pub weapon: WeaponConfig,
+    //every random decision of the simulation comes from here, see `seed`
+    rng: Rng,
+    seed: u64,
+    fx_rng: Rng,
+    particles: ParticlePool,
+    //since the last `advance`
+    explosions: Vec<Explosion>,
+    //input of the current run, step by step
+    recording: Replay,
+    //when set the input comes from a replay instead of `input`
+    playback: Option<Playback>,
+    state: GameState,
+    state_timer: f64,
+    accumulator: f64,
+    lives: u32,
+    invulnerable: f64,
+    last_shoot: f64,
+    saucer_timer: f64,
+    level: u32,
+    time: f64,
+    score: u32,
+    multiplier: u32,
+    last_kill: f64,
+    next_extra_life: u32,
+    //timed power ups the ship has, at most one of each kind
+    effects: Vec<Effect>,
+    //time in ms left before the next jump
+    hyperspace_cooldown: f64,
+    //energy shield: charge from 0 to 1 and time in ms it stays down after running out
+    energy: f64,
+    shield_cooldown: f64,
+    shield_up: bool,
+    //copies of the world while playing a replay back, every CHECKPOINT_STEPS, see `seek`
+    checkpoints: Vec<World>,
+}
+
+impl Entity {
+    fn process_player_acc(&mut self, mouse: Point2<f64>) {
+        //get center of player
+        let player_pos = self.get_pos_center();
+
+        //calcule acceleration vector based on mouse position
+        let dir_vector = mouse - player_pos;
+        let player_acc: Vector2<f64> = dir_vector.normalize() * THRUST;
+        self.acc = player_acc;
+    }
+
+    fn process_player_rot(&mut self, mouse: Point2<f64>) {
+        //get center of player
+        let player_pos = self.pos.coords + self.object.dimentions() / 2.0;
+        let dir_vector = mouse - player_pos;
+        //calcule rotation based on mouse position
+        self.rotation = {
+            let rotation = trig::acos(dir_vector.y / dir_vector.coords.norm());
+            if dir_vector.x < 0.0 {
+                -rotation
+            } else {
+                rotation
+            }
+        };
+    }
+
+    //where the nose of the ship points
+    pub(crate) fn heading(&self) -> Vector2<f64> {
+        trig::rotation(-self.rotation) * Vector2::y()
+    }
+
+    fn process_teleport(&mut self) {
+        let old = self.pos;
+        if self.pos.x > 1000.0 || self.pos.x < -1000.0 {
+            self.pos.x = -self.pos.x.signum() * 1000.0;
+        }
+        if self.pos.y > 1000.0 || self.pos.y < -1000.0 {
+            self.pos.y = -self.pos.y.signum() * 1000.0;
+        }
+        //the previous position jumps together so the interpolation doesn't cross the map
+        self.prev_pos += self.pos - old;
+    }
+}
+
+impl World {
+    pub fn new(seed: u64) -> Self {
+        let rng = Rng::new(seed);
+
+        let player = World::spawn_player();
+        let map_dim = Vector2::new(2000.0, 2000.0);
+
+        Self {
+            player,
+            entities: vec![],
+            map_dim,
+            grid: Grid::new(map_dim, GRID_CELL),
+            input: Default::default(),
+            weapon: Default::default(),
+            rng,
+            seed,
+            fx_rng: Rng::new(seed ^ FX_SEED),
+            particles: Default::default(),
+            explosions: vec![],
+            recording: Replay::new(seed),
+            playback: None,
+            state: GameState::Title,
+            state_timer: 0.0,
+            accumulator: 0.0,
+            lives: START_LIVES,
+            invulnerable: 0.0,
+            last_shoot: f64::NEG_INFINITY,
+            saucer_timer: SAUCER_TIME,
+            level: 1,
+            time: 0.0,
+            score: 0,
+            multiplier: 1,
+            last_kill: f64::NEG_INFINITY,
+            next_extra_life: EXTRA_LIFE_SCORE,
+            effects: vec![],
+            hyperspace_cooldown: 0.0,
+            energy: 1.0,
+            shield_cooldown: 0.0,
+            shield_up: false,
+            checkpoints: vec![],
+        }
+    }
+
+    //seed of the current run, or of the next one while on the title screen
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    //fixes the seed of the next run (daily challenge, replays...)
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    //starts the recorded run over, the live input is ignored from now on
+    pub fn from_replay(replay: impl Into<Rc<Replay>>) -> World {
+        let replay = replay.into();
+        let mut world = World::new(replay.seed);
+        world.reset();
+        world.set_state(GameState::Playing);
+        world.playback = Some(Playback::new(replay));
+        world
+    }
+
+    //recording of the current (or last) run
+    pub fn replay(&self) -> &Replay {
+        &self.recording
+    }
+
+    pub fn playback(&self) -> Option<&Playback> {
+        self.playback.as_ref()
+    }
+
+    //replays can only run forward, so seeking runs it from the closest checkpoint before the
+    //tick, or from where it is when that is closer
+    pub fn seek(&mut self, tick: usize) {
+        let Some(playback) = &self.playback else {
+            return;
+        };
+
+        let index = (tick / CHECKPOINT_STEPS).min(self.checkpoints.len());
+        let from = index * CHECKPOINT_STEPS;
+        if !(from..=tick).contains(&playback.tick()) {
+            let checkpoints = std::mem::take(&mut self.checkpoints);
+            *self = match index {
+                0 => World::from_replay(playback.replay().clone()),
+                _ => checkpoints[index - 1].clone(),
+            };
+            self.checkpoints = checkpoints;
+        }
+
+        while self.playback.as_ref().unwrap().tick() < tick && !self.playback_finished() {
+            self.step();
+        }
+        self.explosions.clear();
+    }
+
+    //the checkpoints are only made the first time the replay gets to them
+    fn save_checkpoint(&mut self) {
+        let Some(playback) = &self.playback else {
+            return;
+        };
+        let tick = playback.tick();
+        if tick == 0
+            || tick % CHECKPOINT_STEPS != 0
+            || tick / CHECKPOINT_STEPS <= self.checkpoints.len()
+        {
+            return;
+        }
+
+        let checkpoints = std::mem::take(&mut self.checkpoints);
+        let checkpoint = self.clone();
+        self.checkpoints = checkpoints;
+        self.checkpoints.push(checkpoint);
+    }
+
+    pub fn playback_finished(&self) -> bool {
+        self.playback.as_ref().is_some_and(|x| x.is_finished())
+    }
+
+    pub fn explosions(&self) -> &[Explosion] {
+        &self.explosions
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        self.particles.as_slice()
+    }
+
+    //active power ups and their time left, for the hud
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    pub fn has_effect(&self, kind: PowerUpKind) -> bool {
+        self.effects.iter().any(|x| x.kind == kind)
+    }
+
+    //time in ms before the hyperspace can be used again
+    pub fn hyperspace_cooldown(&self) -> f64 {
+        self.hyperspace_cooldown
+    }
+
+    //charge of the energy shield, from 0 to 1
+    pub fn energy(&self) -> f64 {
+        self.energy
+    }
+
+    pub fn shield_up(&self) -> bool {
+        self.shield_up
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    //the multiplier drops back to 1 once the combo window has passed
+    pub fn multiplier(&self) -> u32 {
+        if self.time - self.last_kill <= COMBO_TIME {
+            self.multiplier
+        } else {
+            1
+        }
+    }
+
+    fn add_kill(&mut self, points: u32) {
+        if self.time - self.last_kill <= COMBO_TIME {
+            self.multiplier = (self.multiplier + 1).min(MAX_MULTIPLIER);
+        } else {
+            self.multiplier = 1;
+        }
+        self.last_kill = self.time;
+
+        self.score += points * self.multiplier;
+
+        while self.score >= self.next_extra_life {
+            self.lives += 1;
+            self.next_extra_life += EXTRA_LIFE_SCORE;
+            log::info!("extra life, lives: {}", self.lives);
+        }
+    }
+
+    //the ship always spawns centered on the map
+    fn spawn_player() -> Entity {
+        let mut player = Entity::new(Model::Ship);
+
+        player.speed = Vector2::new(0.0, 0.0);
+        player.pos = Point2::origin() - player.object.dimentions() / 2.0;
+        player.max_speed_sqr = 0.3;
+        player.rotation = 0f64.to_radians();
+        player.snapshot();
+        player
+    }
+
+    //start a fresh run, the asteroids from the title screen are discarded
+    //a run only depends on its seed, not on how long the title screen was shown
+    fn reset(&mut self) {
+        self.rng = Rng::new(self.seed);
+        self.fx_rng = Rng::new(self.seed ^ FX_SEED);
+        self.particles.clear();
+        self.recording = Replay::new(self.seed);
+        self.player = World::spawn_player();
+        self.entities.clear();
+        self.lives = START_LIVES;
+        self.invulnerable = INVULNERABLE_TIME;
+        self.last_shoot = f64::NEG_INFINITY;
+        self.saucer_timer = SAUCER_TIME;
+        self.level = 1;
+        self.time = 0.0;
+        self.score = 0;
+        self.multiplier = 1;
+        self.last_kill = f64::NEG_INFINITY;
+        self.next_extra_life = EXTRA_LIFE_SCORE;
+        self.effects.clear();
+        self.hyperspace_cooldown = 0.0;
+        self.energy = 1.0;
+        self.shield_cooldown = 0.0;
+        self.shield_up = false;
+    }
+
+    fn is_spawn_safe(&self) -> bool {
+        let center = Point2::origin();
+        !self.entities.iter().any(|x| match x {
+            GameEntity::Asteroid { entity, .. } => {
+                let radius = entity.object.dimentions().norm() / 2.0;
+                (entity.get_pos_center() - center).norm() < SAFE_RADIUS + radius
+            }
+            _ => false,
+        })
+    }
+
+    fn respawn_player(&mut self) {
+        self.player = World::spawn_player();
+        self.invulnerable = INVULNERABLE_TIME;
+        self.set_state(GameState::Playing);
+    }
+
+    fn set_state(&mut self, state: GameState) {
+        log::info!("game state: {:?} -> {:?}", self.state, state);
+        self.state = state;
+        self.state_timer = 0.0;
+    }
+
+    //random direction, length between min and max
+    fn random_speed(rng: &mut Rng, min: f64, max: f64) -> Vector2<f64> {
+        trig::rotation(rng.range(0.0, TAU)) * Vector2::new(rng.range(min, max), 0.0)
+    }
+
+    //dust flying out of a destroyed asteroid, bigger asteroids leave more of it
+    fn emit_debris(&mut self, asteroid: usize, size: u32) {
+        let asteroid = self.entities[asteroid].get_entity();
+        let (center, drift, color) = (asteroid.get_pos_center(), asteroid.speed, asteroid.color);
+        self.explosions.push(Explosion {
+            pos: center,
+            strength: 0.1 * size as f64,
+        });
+        for _ in 0..4 + 6 * size {
+            let speed = World::random_speed(&mut self.fx_rng, 0.05, 0.3) + drift;
+            let ttl = self.fx_rng.range(400.0, 900.0);
+            self.particles
+                .emit(Particle::point(center, speed, ttl, color));
+        }
+    }
+
+    //the ship breaks in the segments of its outline, spinning away from the center
+    fn emit_wreck(&mut self) {
+        let center = self.player.get_pos_center();
+        let outline = self.player.outline();
+        self.explosions.push(Explosion {
+            pos: center,
+            strength: SHIP_EXPLOSION,
+        });
+        for (i, a) in outline.iter().enumerate() {
+            let b = outline[(i + 1) % outline.len()];
+            let middle = a + (b - a) / 2.0;
+            let speed =
+                (middle - center).normalize() * self.fx_rng.range(0.02, 0.08) + self.player.speed;
+            let spin = self.fx_rng.range(-0.005, 0.005);
+            self.particles.emit(Particle::line(
+                *a,
+                b,
+                speed,
+                spin,
+                DYING_TIME,
+                self.player.color,
+            ));
+        }
+    }
+
+    //flash of particles where the ship leaves and where it comes back
+    fn emit_warp(&mut self) {
+        let center = self.player.get_pos_center();
+        for _ in 0..16 {
+            let speed = World::random_speed(&mut self.fx_rng, 0.05, 0.2);
+            let ttl = self.fx_rng.range(200.0, 500.0);
+            self.particles
+                .emit(Particle::point(center, speed, ttl, HYPERSPACE_COLOR));
+        }
+    }
+
+    //the ship vanishes and shows up somewhere else, standing still, or breaks apart
+    fn hyperspace(&mut self) -> bool {
+        self.hyperspace_cooldown = HYPERSPACE_COOLDOWN;
+        self.emit_warp();
+
+        if self.rng.next_f64() < HYPERSPACE_FAIL_CHANCE {
+            log::info!("hyperspace failed");
+            return false;
+        }
+
+        let center = World::random_point(&mut self.rng, self.map_dim);
+        self.player.pos = center - self.player.object.dimentions() / 2.0;
+        self.player.speed = Vector2::zeros();
+        self.player.acc = Vector2::zeros();
+        //no interpolation across the map
+        self.player.snapshot();
+        self.emit_warp();
+        true
+    }
+
+    //raised while the key is held, as long as there is energy left
+    fn update_shield(&mut self, delta: f64) {
+        self.shield_cooldown = (self.shield_cooldown - delta).max(0.0);
+
+        self.shield_up = self.input.actions[Action::Shield].is_down()
+            && self.shield_cooldown <= 0.0
+            && self.energy > 0.0;
+
+        if self.shield_up {
+            self.energy = (self.energy - delta / SHIELD_DRAIN).max(0.0);
+            if self.energy <= 0.0 {
+                self.shield_cooldown = SHIELD_COOLDOWN;
+            }
+        } else if self.shield_cooldown <= 0.0 {
+            self.energy = (self.energy + delta / SHIELD_RECHARGE).min(1.0);
+        }
+    }
+
+    //flame left behind the ship while it accelerates
+    fn emit_exhaust(&mut self) {
+        let back = self.player.heading() * -self.player.object.dimentions().y / 2.0;
+        let pos = self.player.get_pos_center() + back;
+        let speed = back.normalize() * self.fx_rng.range(0.1, 0.25)
+            + World::random_speed(&mut self.fx_rng, 0.0, 0.03)
+            + self.player.speed;
+        let ttl = self.fx_rng.range(150.0, 300.0);
+        self.particles
+            .emit(Particle::point(pos, speed, ttl, EXHAUST_COLOR));
+    }
+
+    //one bullet towards `aim`, or three in a fan with the spread power up
+    fn spawn_bullet(
+        player: &Entity,
+        aim: Vector2<f64>,
+        weapon: &WeaponConfig,
+        spread: bool,
+    ) -> Vec<GameEntity> {
+        let nav_center: Vector2<f64> = player.get_pos_center().coords;
+        let rot = trig::rotation(-player.rotation);
+        let up = rot * (Vector2::y() * player.object.dimentions().y / 2.0);
+
+        let coors = nav_center + up;
+
+        let dir_vector = aim.normalize();
+
+        let angles: &[f64] = if spread {
+            &[-SPREAD_ANGLE, 0.0, SPREAD_ANGLE]
+        } else {
+            &[0.0]
+        };
+
+        angles
+            .iter()
+            .map(|angle| {
+                let dir_vector = trig::rotation(angle.to_radians()) * dir_vector;
+                let mut bullet = Entity::new(Model::Bullet);
+                // bullet.object.scale = 5.0;
+                bullet.pos = (coors - bullet.object.dimentions() / 2.0).into();
+                bullet.speed = dir_vector * weapon.bullet_speed + player.speed;
+                bullet.snapshot();
+                GameEntity::Bullet {
+                    entity: bullet,
+                    hit: false,
+                    ttl: weapon.bullet_ttl,
+                    owner: Owner::Player,
+                }
+            })
+            .collect()
+    }
+
+    //sometimes a destroyed asteroid leaves a pickup drifting where it was
+    fn drop_power_up(&mut self, asteroid: usize, new_entities: &mut Vec<GameEntity>) {
+        if self.rng.next_f64() >= POWER_UP_CHANCE {
+            return;
+        }
+        let kind = PowerUpKind::ALL[self.rng.next_u32() as usize % PowerUpKind::ALL.len()];
+
+        let asteroid = self.entities[asteroid].get_entity();
+        let mut power_up = Entity::new(Model::PowerUp);
+        power_up.color = kind.color();
+        power_up.pos = asteroid.get_pos_center() - power_up.object.dimentions() / 2.0;
+        power_up.speed = asteroid.speed * 0.5;
+        power_up.snapshot();
+
+        new_entities.push(GameEntity::PowerUp {
+            entity: power_up,
+            kind,
+            hit: false,
+            ttl: POWER_UP_TTL,
+        });
+    }
+
+    fn apply_power_up(&mut self, kind: PowerUpKind) {
+        log::info!("power up: {}", kind.name());
+        let Some(duration) = kind.duration() else {
+            self.lives += 1;
+            return;
+        };
+
+        //picking one already active just renews it
+        match self.effects.iter_mut().find(|x| x.kind == kind) {
+            Some(effect) => effect.time_left = duration,
+            None => self.effects.push(Effect {
+                kind,
+                time_left: duration,
+            }),
+        }
+    }
+
+    fn collect_power_ups(&mut self) {
+        let bounds = self.player.bounds();
+        for i in 0..self.entities.len() {
+            let GameEntity::PowerUp {
+                entity,
+                kind,
+                hit: false,
+                ..
+            } = &self.entities[i]
+            else {
+                continue;
+            };
+
+            if circles_overlap(bounds, entity.bounds(), self.map_dim)
+                && self.player.hit_wrapped(entity, self.map_dim)
+            {
+                let kind = *kind;
+                self.entities[i].set_hit(true);
+                self.apply_power_up(kind);
+            }
+        }
+    }
+
+    fn update_effects(&mut self, delta: f64) {
+        for effect in self.effects.iter_mut() {
+            effect.time_left -= delta;
+        }
+        self.effects.retain(|x| x.time_left > 0.0);
+    }
+
+    fn spawn_saucer_bullet(center: Point2<f64>, dir: Vector2<f64>) -> GameEntity {
+        let mut bullet = Entity::new(Model::Bullet);
+        bullet.color = SAUCER_COLOR;
+        bullet.pos = center - bullet.object.dimentions() / 2.0;
+        bullet.speed = dir * SAUCER_BULLET_SPEED;
+        bullet.snapshot();
+        GameEntity::Bullet {
+            entity: bullet,
+            hit: false,
+            ttl: SAUCER_BULLET_TTL,
+            owner: Owner::Saucer,
+        }
+    }
+
+    //saucers enter from the left or right edge, the small ones get common as the score grows
+    fn spawn_saucer(&mut self) -> GameEntity {
+        let small_chance = (self.score as f64 / 40000.0).clamp(0.2, 0.8);
+        let size = if self.rng.next_f64() < small_chance {
+            SaucerSize::Small
+        } else {
+            SaucerSize::Large
+        };
+
+        let mut saucer = Entity::new(Model::Saucer);
+        saucer.object.scale = size.scale();
+        saucer.color = SAUCER_COLOR;
+
+        let half = self.map_dim / 2.0;
+        let y = self.rng.range(-half.y, half.y);
+        if self.rng.next_f64() < 0.5 {
+            saucer.pos = Point2::new(-half.x, y);
+            saucer.speed = Vector2::new(size.speed(), 0.0);
+        } else {
+            saucer.pos = Point2::new(half.x - saucer.object.dimentions().x, y);
+            saucer.speed = Vector2::new(-size.speed(), 0.0);
+        }
+        saucer.snapshot();
+
+        log::info!("saucer: {:?}", size);
+        GameEntity::Saucer {
+            entity: saucer,
+            size,
+            hit: false,
+            travelled: 0.0,
+            fire_timer: size.fire_time(),
+            turn_timer: SAUCER_TURN_TIME,
+        }
+    }
+
+    //spawns, steers and fires the saucers, they only show up while playing
+    fn update_saucers(&mut self, delta: f64) {
+        if !self
+            .entities
+            .iter()
+            .any(|x| matches!(x, GameEntity::Saucer { .. }))
+        {
+            self.saucer_timer -= delta;
+            if self.saucer_timer <= 0.0 {
+                self.saucer_timer = SAUCER_TIME;
+                let saucer = self.spawn_saucer();
+                self.entities.push(saucer);
+            }
+        }
+
+        self.fly_saucers(delta, true);
+    }
+
+    //the saucers keep crossing the screen in every state until they leave it
+    //they only fire while there is a ship to fire at
+    fn fly_saucers(&mut self, delta: f64, fire: bool) {
+        let map_width = self.map_dim.x;
+        self.entities.retain(|x| {
+            !matches!(x, GameEntity::Saucer { entity, travelled, .. }
+                if *travelled >= map_width - entity.object.dimentions().x)
+        });
+
+        let mut shots = vec![];
+        for entity in self.entities.iter_mut() {
+            let GameEntity::Saucer {
+                entity,
+                size,
+                travelled,
+                fire_timer,
+                turn_timer,
+                ..
+            } = entity
+            else {
+                continue;
+            };
+
+            *travelled += entity.speed.x.abs() * delta;
+
+            //zig-zag: every so often go up, down or straight ahead
+            *turn_timer -= delta;
+            if *turn_timer <= 0.0 {
+                *turn_timer = SAUCER_TURN_TIME;
+                let turn = (self.rng.next_u32() % 3) as f64 - 1.0;
+                entity.speed.y = turn * size.speed();
+            }
+
+            *fire_timer -= delta;
+            if !fire || *fire_timer > 0.0 {
+                continue;
+            }
+            *fire_timer = size.fire_time();
+
+            let center = entity.get_pos_center();
+            //aim at the closest copy of the ship, it may be just across the edge of the map
+            let player = center + wrapped_delta(center, self.player.get_pos_center(), self.map_dim);
+            let dir = match size {
+                SaucerSize::Large => trig::rotation(self.rng.range(0.0, TAU)) * Vector2::x(),
+                SaucerSize::Small => {
+                    let error = self
+                        .rng
+                        .range(-SAUCER_AIM_ERROR, SAUCER_AIM_ERROR)
+                        .to_radians();
+                    trig::rotation(error)
+                        * lead_direction(center, player, self.player.speed, SAUCER_BULLET_SPEED)
+                }
+            };
+            shots.push(World::spawn_saucer_bullet(center, dir));
+        }
+        self.entities.append(&mut shots);
+    }
+
+    fn random_point(rng: &mut Rng, map_dim: Vector2<f64>) -> Point2<f64> {
+        let half = map_dim / 2.0;
+        Point2::new(rng.range(-half.x, half.x), rng.range(-half.y, half.y))
+    }
+
+    fn spawn_asteroid(&mut self) -> GameEntity {
+        let mut asteroid = Entity::new(Model::Asteroid);
+
+        let max_speed = 0.6;
+
+        let pos = World::random_point(&mut self.rng, self.map_dim);
+        asteroid.speed = Vector2::new(
+            self.rng.range(0.0, max_speed),
+            self.rng.range(0.0, max_speed),
+        );
+        asteroid.pos = pos;
+        asteroid.object.scale = asteroid_scale(ASTEROID_MAX_SIZE);
+        asteroid.rotation = self.rng.range(0.0, 360.0).to_radians();
+        asteroid.snapshot();
+
+        GameEntity::Asteroid {
+            entity: asteroid,
+            size: ASTEROID_MAX_SIZE,
+            hit: false,
+        }
+    }
+
+    fn spawn_wave(&mut self) {
+        if self
+            .entities
+            .iter()
+            .any(|x| matches!(x, GameEntity::Asteroid { .. }))
+        {
+            return;
+        }
+
+        let qtd = 2 + self.level;
+        //let qtd = 1;
+        for _ in 0..qtd {
+            let entity = self.spawn_asteroid();
+            self.entities.push(entity);
+        }
+        self.level += 1;
+    }
+
+    fn can_shoot(&self) -> bool {
+        let bullets = self
+            .entities
+            .iter()
+            .filter(|x| {
+                matches!(
+                    x,
+                    GameEntity::Bullet {
+                        owner: Owner::Player,
+                        ..
+                    }
+                )
+            })
+            .count();
+        let cooldown = if self.has_effect(PowerUpKind::RapidFire) {
+            self.weapon.cooldown / RAPID_FIRE
+        } else {
+            self.weapon.cooldown
+        };
+        self.time - self.last_shoot >= cooldown && bullets < self.weapon.max_bullets
+    }
+
+    fn update_player(&mut self, delta: f64) {
+        self.player.snapshot();
+
+        //what the controls ask for: thrusting, firing and where to shoot
+        let actions = &self.input.actions;
+        let thrust = actions[Action::Thrust].is_down();
+        let fire = actions[Action::Fire].is_down();
+        let aim = match self.input.scheme {
+            ControlScheme::Mouse => {
+                let pointer = self.input.pointer;
+                if thrust {
+                    self.player.process_player_acc(pointer);
+                }
+                if thrust || fire {
+                    self.player.process_player_rot(pointer);
+                }
+                pointer - self.player.get_pos_center()
+            }
+            ControlScheme::Keyboard => {
+                if actions[Action::RotateLeft].is_down() {
+                    self.player.rotation -= ROTATION_SPEED * delta;
+                }
+                if actions[Action::RotateRight].is_down() {
+                    self.player.rotation += ROTATION_SPEED * delta;
+                }
+                let heading = self.player.heading();
+                if thrust {
+                    self.player.acc = heading * THRUST;
+                }
+                heading
+            }
+        };
+
+        if thrust {
+            self.emit_exhaust();
+        } else if self.player.speed.magnitude() > 0.001 {
+            let arrasto: Vector2<f64> = self.player.speed.normalize() * -0.00007;
+            self.player.acc = arrasto;
+        } else {
+            self.player.acc = Vector2::default();
+            self.player.speed = Vector2::default();
+        }
+
+        if fire && self.can_shoot() {
+            let spread = self.has_effect(PowerUpKind::Spread);
+            let mut bullets = World::spawn_bullet(&self.player, aim, &self.weapon, spread);
+            self.entities.append(&mut bullets);
+            self.last_shoot = self.time;
+        }
+
+        self.player.update_physics(delta);
+    }
+
+    //debris plus two smaller asteroids, unless it was already the smallest size
+    fn split_asteroid(&mut self, i: usize, size: u32, new_entities: &mut Vec<GameEntity>) {
+        self.emit_debris(i, size);
+        if size <= 1 {
+            return;
+        }
+        let size = size - 1;
+        for _ in 0..2 {
+            let mut asteroid = self.entities[i].get_entity().clone();
+            asteroid.object.scale = asteroid_scale(size);
+            asteroid.speed = Vector2::new(self.rng.range(0.0, 0.6), self.rng.range(0.0, 0.6));
+            asteroid.rotation = self.rng.range(0.0, 360.0).to_radians();
+
+            new_entities.push(GameEntity::Asteroid {
+                entity: asteroid,
+                size,
+                hit: false,
+            });
+        }
+    }
+
+    //moves every entity, resolves bullet hits and removes what was destroyed
+    //returns true if the player got hit by an asteroid, a saucer or a saucer bullet
+    fn update_entities(&mut self, delta: f64, check_player: bool) -> bool {
+        self.particles.update(delta, self.map_dim);
+
+        //physics loop
+        for entity in self.entities.iter_mut() {
+            if let GameEntity::Bullet { ttl, .. } | GameEntity::PowerUp { ttl, .. } = entity {
+                *ttl -= delta;
+            }
+            let entity = entity.get_entity_mut();
+            entity.snapshot();
+            entity.update_physics(delta);
+        }
+
+        self.grid.clear();
+        for (j, entity) in self.entities.iter().enumerate() {
+            if let GameEntity::Bullet { entity, .. } = entity {
+                let (center, radius) = entity.bounds();
+                self.grid.insert(j, center, radius);
+            }
+        }
+
+        //everything bullets can destroy
+        let index_target: Vec<usize> = self
+            .entities
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| matches!(x, GameEntity::Asteroid { .. } | GameEntity::Saucer { .. }))
+            .map(|x| x.0)
+            .collect();
+
+        let player_bounds = self.player.bounds();
+        let mut player_hit = false;
+        let mut new_entities: Vec<GameEntity> = vec![];
+        let mut candidates: Vec<usize> = vec![];
+        for i in index_target {
+            let bounds = self.entities[i].get_entity().bounds();
+            self.grid.query(bounds.0, bounds.1, &mut candidates);
+
+            for j in candidates.iter().copied() {
+                if self.entities[i].is_hit() || self.entities[j].is_hit() {
+                    continue;
+                }
+
+                let GameEntity::Bullet { owner, .. } = self.entities[j] else {
+                    continue;
+                };
+                if owner == Owner::Saucer && matches!(self.entities[i], GameEntity::Saucer { .. }) {
+                    continue;
+                }
+
+                if !circles_overlap(bounds, self.entities[j].get_entity().bounds(), self.map_dim) {
+                    continue;
+                }
+
+                if self.entities[i]
+                    .get_entity()
+                    .hit_wrapped(self.entities[j].get_entity(), self.map_dim)
+                {
+                    self.entities.get_mut(i).unwrap().set_hit(true);
+                    self.entities.get_mut(j).unwrap().set_hit(true);
+
+                    match self.entities[i] {
+                        GameEntity::Asteroid { size, .. } => {
+                            //asteroids shot by a saucer split but give no points
+                            if owner == Owner::Player {
+                                self.add_kill(asteroid_points(size));
+                                self.drop_power_up(i, &mut new_entities);
+                            }
+                            self.split_asteroid(i, size, &mut new_entities);
+                        }
+                        GameEntity::Saucer { size, .. } => {
+                            log::info!("saucer destroyed: {:?}", size);
+                            self.add_kill(size.points());
+                            self.emit_debris(i, 2);
+                        }
+                        GameEntity::Bullet { .. } | GameEntity::PowerUp { .. } => {}
+                    }
+                }
+            }
+
+            if check_player
+                && !player_hit
+                && !self.entities[i].is_hit()
+                && circles_overlap(bounds, player_bounds, self.map_dim)
+                && self
+                    .player
+                    .hit_wrapped(self.entities[i].get_entity(), self.map_dim)
+            {
+                player_hit = true;
+                //ramming a saucer takes it down too
+                if let GameEntity::Saucer { .. } = self.entities[i] {
+                    self.entities[i].set_hit(true);
+                    self.emit_debris(i, 2);
+                }
+            }
+        }
+
+        //saucer bullets hitting the ship
+        if check_player && !player_hit {
+            self.grid
+                .query(player_bounds.0, player_bounds.1, &mut candidates);
+            for j in candidates.iter().copied() {
+                let GameEntity::Bullet {
+                    entity,
+                    hit: false,
+                    owner: Owner::Saucer,
+                    ..
+                } = &self.entities[j]
+                else {
+                    continue;
+                };
+
+                if circles_overlap(player_bounds, entity.bounds(), self.map_dim)
+                    && self.player.hit_wrapped(entity, self.map_dim)
+                {
+                    self.entities[j].set_hit(true);
+                    player_hit = true;
+                    break;
+                }
+            }
+        }
+
+        new_entities.append(&mut self.entities);
+        self.entities = new_entities
+            .into_iter()
+            .filter(|x| {
+                !(x.is_hit()
+                    || matches!(x, GameEntity::Bullet { ttl, .. } | GameEntity::PowerUp { ttl, .. } if *ttl <= 0.0))
+            })
+            .collect();
+
+        for entity in self.entities.iter_mut() {
+            entity.get_entity_mut().process_teleport();
+        }
+
+        player_hit
+    }
+
+    //attract mode, the asteroids drift behind the title until the player starts
+    fn update_title(&mut self, delta: f64) {
+        self.spawn_wave();
+        self.fly_saucers(delta, false);
+        self.update_entities(delta, false);
+
+        if self.input.actions[Action::Start].take_pressed() {
+            self.reset();
+            self.set_state(GameState::Playing);
+        }
+    }
+
+    fn update_playing(&mut self, delta: f64) {
+        //start pauses too, a gamepad has a single button for both
+        let pause = self.input.actions[Action::Pause].take_pressed();
+        if self.input.actions[Action::Start].take_pressed() | pause {
+            self.set_state(GameState::Paused);
+            return;
+        }
+
+        self.time += delta;
+        self.spawn_wave();
+        self.update_player(delta);
+        self.update_saucers(delta);
+
+        self.invulnerable = (self.invulnerable - delta).max(0.0);
+        let check_player = self.invulnerable <= 0.0;
+
+        self.update_effects(delta);
+        self.update_shield(delta);
+
+        self.hyperspace_cooldown = (self.hyperspace_cooldown - delta).max(0.0);
+        if self.input.actions[Action::Hyperspace].take_pressed()
+            && self.hyperspace_cooldown <= 0.0
+            && !self.hyperspace()
+        {
+            self.kill_player();
+            return;
+        }
+
+        let hit = self.update_entities(delta, check_player);
+        self.collect_power_ups();
+
+        if hit && self.shield_up {
+            //the energy shield just keeps the hits off while it lasts
+        } else if hit && self.has_effect(PowerUpKind::Shield) {
+            //the shield takes the hit and is gone
+            self.effects.retain(|x| x.kind != PowerUpKind::Shield);
+            self.invulnerable = SHIELD_GRACE;
+        } else if hit {
+            self.kill_player();
+        }
+    }
+
+    fn kill_player(&mut self) {
+        self.lives = self.lives.saturating_sub(1);
+        log::info!("player has died, lives left: {}", self.lives);
+        self.effects.clear();
+        self.shield_up = false;
+        self.emit_wreck();
+        self.set_state(GameState::Dying);
+    }
+
+    //nothing moves, so the drawing must not interpolate either
+    fn freeze(&mut self) {
+        self.player.snapshot();
+        self.particles.freeze();
+        for entity in self.entities.iter_mut() {
+            entity.get_entity_mut().snapshot();
+        }
+    }
+
+    fn update_paused(&mut self) {
+        self.freeze();
+
+        if self.input.actions[Action::Pause].take_pressed()
+            || self.input.actions[Action::Start].take_pressed()
+        {
+            self.set_state(GameState::Playing);
+        }
+    }
+
+    fn update_dying(&mut self, delta: f64) {
+        self.fly_saucers(delta, false);
+        self.update_entities(delta, false);
+
+        self.state_timer += delta;
+        if self.state_timer < DYING_TIME {
+            return;
+        }
+
+        if self.lives == 0 {
+            self.set_state(GameState::GameOver);
+        } else if self.is_spawn_safe() {
+            self.respawn_player();
+        }
+    }
+
+    fn update_game_over(&mut self, delta: f64) {
+        self.fly_saucers(delta, false);
+        self.update_entities(delta, false);
+
+        if self.input.actions[Action::Start].take_pressed() {
+            self.seed = self.rng.next_u64();
+            self.set_state(GameState::Title);
+        }
+    }
+
+    fn update(&mut self, delta: f64) {
+        match self.state {
+            GameState::Title => self.update_title(delta),
+            GameState::Playing => self.update_playing(delta),
+            GameState::Paused => self.update_paused(),
+            GameState::Dying => self.update_dying(delta),
+            GameState::GameOver => self.update_game_over(delta),
+        }
+    }
+
+    //delta is the real time since the last frame, it is consumed in fixed steps
+    pub fn advance(&mut self, delta: f64) {
+        self.advance_with(delta, |_| {});
+    }
+
+    //like `advance`, with `latch` called right before every step to update the input
+    //frames shorter than a step run none, so latching once per frame would lose presses
+    pub fn advance_with(&mut self, delta: f64, mut latch: impl FnMut(&mut UserInput)) {
+        self.explosions.clear();
+        self.accumulator += delta.max(0.0);
+
+        //counted, a clamped accumulator can round to one step short
+        for _ in 0..MAX_STEPS {
+            if self.accumulator < FIXED_STEP {
+                return;
+            }
+            latch(&mut self.input);
+            self.step();
+            self.accumulator -= FIXED_STEP;
+        }
+        //the time left is not caught up later, only the part of a step is kept
+        self.accumulator %= FIXED_STEP;
+    }
+
+    //runs exactly one fixed simulation step
+    pub fn step(&mut self) {
+        self.save_checkpoint();
+        if let Some(playback) = &mut self.playback {
+            match playback.next_input() {
+                Some(input) => self.input = input.clone(),
+                None => {
+                    self.freeze();
+                    return;
+                }
+            }
+        }
+
+        //rounded the same way live and when played back, so both runs see the same pointer
+        self.input.quantize();
+
+        //the input is recorded before the step changes it (a press becomes a hold)
+        let in_run = matches!(
+            self.state,
+            GameState::Playing | GameState::Paused | GameState::Dying
+        );
+        if in_run && self.playback.is_none() {
+            self.recording.push(&self.input);
+        }
+
+        self.update(FIXED_STEP);
+    }
+
+    //how far (0..1) the current frame is between the last two simulation steps
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / FIXED_STEP
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.iter().map(|x| x.get_entity())
+    }
+
+    //the ship, as long as it is alive
+    pub fn player(&self) -> Option<&Entity> {
+        match self.state {
+            GameState::Playing | GameState::Paused => Some(&self.player),
+            GameState::Title | GameState::Dying | GameState::GameOver => None,
+        }
+    }
+
+    //false while the invulnerable ship is in the off phase of its blink
+    pub fn player_visible(&self) -> bool {
+        !(self.invulnerable > 0.0 && (self.invulnerable / BLINK_TIME) as u32 % 2 == 1)
+    }
+}
+
+//hand-built scenes, so the tests can put things exactly where they want them
+//positions are the centre of the entity
+impl World {
+    //removes every asteroid, saucer, bullet and pickup
+    //while playing a new wave comes as soon as there is no asteroid left
+    pub fn clear_entities(&mut self) {
+        self.entities.clear();
+    }
+
+    pub fn place_player(&mut self, center: Point2<f64>) {
+        self.player.pos = center - self.player.object.dimentions() / 2.0;
+        self.player.speed = Vector2::zeros();
+        self.player.snapshot();
+    }
+
+    pub fn add_asteroid(&mut self, center: Point2<f64>, speed: Vector2<f64>, size: u32) {
+        let mut asteroid = Entity::new(Model::Asteroid);
+        asteroid.object.scale = asteroid_scale(size);
+        asteroid.pos = center - asteroid.object.dimentions() / 2.0;
+        asteroid.speed = speed;
+        asteroid.snapshot();
+        self.entities.push(GameEntity::Asteroid {
+            entity: asteroid,
+            size,
+            hit: false,
+        });
+    }
+
+    pub fn add_bullet(&mut self, center: Point2<f64>, speed: Vector2<f64>, owner: Owner) {
+        let mut bullet = Entity::new(Model::Bullet);
+        let ttl = match owner {
+            Owner::Player => self.weapon.bullet_ttl,
+            Owner::Saucer => {
+                bullet.color = SAUCER_COLOR;
+                SAUCER_BULLET_TTL
+            }
+        };
+        bullet.pos = center - bullet.object.dimentions() / 2.0;
+        bullet.speed = speed;
+        bullet.snapshot();
+        self.entities.push(GameEntity::Bullet {
+            entity: bullet,
+            hit: false,
+            ttl,
+            owner,
+        });
+    }
+
+    pub fn add_saucer(&mut self, center: Point2<f64>, speed: Vector2<f64>, size: SaucerSize) {
+        let mut saucer = Entity::new(Model::Saucer);
+        saucer.object.scale = size.scale();
+        saucer.color = SAUCER_COLOR;
+        saucer.pos = center - saucer.object.dimentions() / 2.0;
+        saucer.speed = speed;
+        saucer.snapshot();
+        self.entities.push(GameEntity::Saucer {
+            entity: saucer,
+            size,
+            hit: false,
+            travelled: 0.0,
+            fire_timer: size.fire_time(),
+            turn_timer: SAUCER_TURN_TIME,
+        });
+    }
+
+    pub fn add_power_up(&mut self, center: Point2<f64>, kind: PowerUpKind) {
+        let mut power_up = Entity::new(Model::PowerUp);
+        power_up.color = kind.color();
+        power_up.pos = center - power_up.object.dimentions() / 2.0;
+        power_up.snapshot();
+        self.entities.push(GameEntity::PowerUp {
+            entity: power_up,
+            kind,
+            hit: false,
+            ttl: POWER_UP_TTL,
+        });
+    }
+}
@@ -0,0 +1,145 @@
+use nalgebra::{Point2, Vector2};
+use trunk_template::sim::{
+    circles_overlap, wrap_offset, wrapped_copies, wrapped_delta, Entity, Grid, Model,
+};
+
+fn map_dim() -> Vector2<f64> {
+    Vector2::new(2000.0, 2000.0)
+}
+
+#[test]
+fn bounding_circle_holds_the_hit_box() {
+    let mut asteroid = Entity::new(Model::Asteroid);
+    asteroid.object.scale = 6.0;
+    asteroid.pos = Point2::new(-300.0, 150.0);
+
+    let (center, radius) = asteroid.bounds();
+    for step in 0..16 {
+        asteroid.rotation = step as f64 * 0.4;
+        for point in Entity::transform_all(&asteroid).column_iter() {
+            assert!((Point2::from(point.into_owned()) - center).norm() <= radius + 1e-9);
+        }
+    }
+}
+
+#[test]
+fn distances_wrap_around_the_map() {
+    let a = Point2::new(990.0, -995.0);
+    let b = Point2::new(-990.0, 995.0);
+
+    assert_eq!(wrapped_delta(a, b, map_dim()), Vector2::new(20.0, -10.0));
+    assert!(circles_overlap((a, 10.0), (b, 15.0), map_dim()));
+    assert!(!circles_overlap((a, 5.0), (b, 5.0), map_dim()));
+}
+
+#[test]
+fn grid_finds_neighbours_across_the_edge() {
+    let mut grid = Grid::new(map_dim(), 200.0);
+    grid.insert(0, Point2::new(-995.0, 0.0), 0.0);
+    grid.insert(1, Point2::new(0.0, 0.0), 0.0);
+    grid.insert(2, Point2::new(-995.0, 0.0), 0.0);
+
+    let mut out = vec![];
+    grid.query(Point2::new(990.0, 0.0), 20.0, &mut out);
+    assert_eq!(out, vec![0, 2]);
+
+    grid.query(Point2::new(0.0, 0.0), 5000.0, &mut out);
+    assert_eq!(out, vec![0, 1, 2]);
+
+    grid.clear();
+    grid.query(Point2::new(0.0, 0.0), 5000.0, &mut out);
+    assert!(out.is_empty());
+}
+
+fn small_asteroid() -> Entity {
+    let mut asteroid = Entity::new(Model::Asteroid);
+    asteroid.object.scale = 2.0;
+    asteroid.snapshot();
+    asteroid
+}
+
+//a bullet at `from` that ended the step at `to`
+fn bullet(from: Point2<f64>, to: Point2<f64>) -> Entity {
+    let mut bullet = Entity::new(Model::Bullet);
+    bullet.pos = from;
+    bullet.snapshot();
+    bullet.pos = to;
+    bullet
+}
+
+#[test]
+fn fast_bullets_do_not_tunnel() {
+    let asteroid = small_asteroid();
+    let center = asteroid.get_pos_center();
+    let left = Point2::new(center.x - 500.0, center.y);
+    let right = Point2::new(center.x + 500.0, center.y);
+
+    //both ends are outside, only the path crosses the asteroid
+    assert!(!asteroid.hit2(&bullet(left, left)));
+    assert!(!asteroid.hit2(&bullet(right, right)));
+    assert!(asteroid.hit2(&bullet(left, right)));
+    assert!(asteroid.hit2(&bullet(right, left)));
+
+    //a path passing by does not count
+    let above = Vector2::new(0.0, asteroid.bounding_radius() + 10.0);
+    assert!(!asteroid.hit2(&bullet(left + above, right + above)));
+}
+
+#[test]
+fn sweep_is_relative_to_the_target() {
+    let mut asteroid = small_asteroid();
+    let center = asteroid.get_pos_center();
+    let radius = asteroid.bounding_radius();
+
+    //the bullet stood still but the asteroid ran over it during the step
+    let point = Point2::new(center.x + radius + 50.0, center.y);
+    asteroid.pos += Vector2::new(2.0 * (radius + 50.0), 0.0);
+    assert!(asteroid.hit2(&bullet(point, point)));
+}
+
+#[test]
+fn copies_cover_every_edge_and_corner() {
+    let map = (Point2::new(-1000.0, -1000.0), Point2::new(1000.0, 1000.0));
+    let copies =
+        |center, radius| wrapped_copies(center, radius, map, map_dim()).collect::<Vec<_>>();
+
+    assert_eq!(copies(Point2::new(0.0, 0.0), 50.0), vec![Vector2::zeros()]);
+    //left edge only
+    assert_eq!(
+        copies(Point2::new(-990.0, 0.0), 50.0),
+        vec![Vector2::zeros(), Vector2::new(2000.0, 0.0)]
+    );
+    //bottom left corner, it shows up in all four corners of the map
+    let corner = copies(Point2::new(-990.0, -990.0), 50.0);
+    assert_eq!(corner.len(), 4);
+    assert!(corner.contains(&Vector2::new(2000.0, 2000.0)));
+
+    //a view wider than the map sees the copies past its edges too
+    let wide = (Point2::new(-2500.0, -1000.0), Point2::new(2500.0, 1000.0));
+    let seen: Vec<_> = wrapped_copies(Point2::new(0.0, 0.0), 50.0, wide, map_dim()).collect();
+    assert_eq!(seen.len(), 3);
+}
+
+#[test]
+fn asteroids_on_the_seam_are_hit_from_either_side() {
+    let mut asteroid = small_asteroid();
+    //the asteroid pokes out of the right edge, the bullet is on the left one
+    asteroid.pos = Point2::new(1000.0 - asteroid.object.dimentions().x / 4.0, 0.0);
+    asteroid.snapshot();
+    let center = asteroid.get_pos_center();
+    let point = Point2::new(center.x - 2000.0, center.y);
+
+    assert!(!asteroid.hit2(&bullet(point, point)));
+    assert!(asteroid.hit_wrapped(&bullet(point, point), map_dim()));
+    assert_eq!(
+        wrap_offset(center, point, map_dim()),
+        Vector2::new(2000.0, 0.0)
+    );
+
+    //and a ship across the corner from it
+    let mut ship = Entity::new(Model::Ship);
+    ship.pos = asteroid.pos - Vector2::new(2000.0, 2000.0);
+    ship.snapshot();
+    assert!(!asteroid.hit2(&ship));
+    assert!(asteroid.hit_wrapped(&ship, map_dim()));
+}
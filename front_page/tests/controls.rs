@@ -0,0 +1,137 @@
+//! Physical inputs to actions, the part of the input handling that doesn't need a browser.
+
+use nalgebra::Vector2;
+use trunk_template::sim::{
+    stick, stick_inputs, Action, Actions, Bindings, ButtonState, Controls, GameState,
+    PhysicalInput, UserInput, World, FIXED_STEP,
+};
+
+fn key(code: &str) -> PhysicalInput {
+    PhysicalInput::Key(code.to_string())
+}
+
+#[test]
+fn latch_goes_through_every_state() {
+    let mut controls = Controls::default();
+    let mut actions = Actions::default();
+
+    assert!(controls.press(key("Space")));
+    //keydown repeats while held
+    assert!(controls.press(key("Space")));
+    controls.latch(&mut actions);
+    assert_eq!(actions[Action::Fire], ButtonState::Pressed);
+    controls.latch(&mut actions);
+    assert_eq!(actions[Action::Fire], ButtonState::Hold);
+
+    assert!(controls.release(&key("Space")));
+    controls.latch(&mut actions);
+    assert_eq!(actions[Action::Fire], ButtonState::Released);
+    controls.latch(&mut actions);
+    assert_eq!(actions[Action::Fire], ButtonState::None);
+
+    //down and up again between two frames still counts as a press
+    controls.press(key("KeyH"));
+    controls.release(&key("KeyH"));
+    controls.latch(&mut actions);
+    assert_eq!(actions[Action::Hyperspace], ButtonState::Pressed);
+    controls.latch(&mut actions);
+    assert_eq!(actions[Action::Hyperspace], ButtonState::Released);
+
+    //two keys for the same action, it stays down until both are up
+    controls.press(key("ArrowUp"));
+    controls.press(key("KeyW"));
+    controls.release(&key("ArrowUp"));
+    controls.latch(&mut actions);
+    assert!(actions[Action::Thrust].is_down());
+
+    assert!(!controls.press(key("KeyZ")));
+}
+
+#[test]
+fn bindings_can_be_changed_and_saved() {
+    let mut bindings = Bindings::default();
+    assert_eq!(bindings.action(&key("Space")), Some(Action::Fire));
+
+    //taken away from fire
+    bindings.bind(key("Space"), Action::Hyperspace);
+    assert_eq!(bindings.action(&key("Space")), Some(Action::Hyperspace));
+    assert!(!bindings.inputs(Action::Fire).any(|x| *x == key("Space")));
+
+    bindings.clear(Action::Shield);
+    assert_eq!(bindings.inputs(Action::Shield).count(), 0);
+    assert_eq!(bindings.action(&key("ShiftLeft")), None);
+
+    let json = bindings.to_json();
+    assert_eq!(Bindings::from_json(&json).unwrap(), bindings);
+    assert!(Bindings::from_json("{").is_err());
+
+    let mut controls = Controls::new(bindings);
+    let mut actions = Actions::default();
+    controls.press(PhysicalInput::Mouse(2));
+    controls.press(key("Space"));
+    controls.latch(&mut actions);
+    assert!(actions[Action::Fire].is_down());
+    assert!(actions[Action::Hyperspace].is_down());
+}
+
+#[test]
+fn gamepads_are_polled_with_a_deadzone() {
+    //small drift around the center is ignored
+    assert_eq!(stick(0.1, -0.2), Vector2::zeros());
+    assert_eq!(stick_inputs(0, 0.2, 0.2).count(), 0);
+    assert!((stick(0.0, -1.0).y + 1.0).abs() < 1e-9);
+
+    let mut controls = Controls::default();
+    let mut actions = Actions::default();
+
+    //stick up and to the left, the A button down
+    let mut inputs: Vec<_> = stick_inputs(0, -0.7, -0.7).collect();
+    inputs.push(PhysicalInput::PadButton(0));
+    assert!(controls.poll(inputs.clone()));
+    controls.latch(&mut actions);
+    assert_eq!(actions[Action::Thrust], ButtonState::Pressed);
+    assert_eq!(actions[Action::RotateLeft], ButtonState::Pressed);
+    assert_eq!(actions[Action::Fire], ButtonState::Pressed);
+
+    //same inputs again are not new presses
+    assert!(!controls.poll(inputs));
+    controls.latch(&mut actions);
+    assert_eq!(actions[Action::Fire], ButtonState::Hold);
+
+    //a key held meanwhile is not released by the poll
+    controls.press(key("Space"));
+    controls.poll(stick_inputs(0, 0.0, -0.9));
+    controls.latch(&mut actions);
+    assert!(actions[Action::Thrust].is_down());
+    assert!(!actions[Action::RotateLeft].is_down());
+    assert!(actions[Action::Fire].is_down());
+
+    controls.release(&key("Space"));
+    controls.poll([]);
+    controls.latch(&mut actions);
+    assert_eq!(actions[Action::Fire], ButtonState::Released);
+    assert_eq!(actions[Action::Thrust], ButtonState::Released);
+}
+
+#[test]
+fn short_frames_keep_presses_for_the_next_step() {
+    let mut world = World::new(42);
+    let mut controls = Controls::default();
+
+    controls.press(key("Enter"));
+    controls.release(&key("Enter"));
+    //a 240hz frame, no step runs and the press waits for one
+    let mut frame = |world: &mut World| {
+        world.advance_with(FIXED_STEP * 0.4, |input: &mut UserInput| {
+            controls.latch(&mut input.actions)
+        });
+    };
+    frame(&mut world);
+    assert_eq!(world.state(), GameState::Title);
+    frame(&mut world);
+    assert_eq!(world.state(), GameState::Title);
+
+    //the third frame completes a step
+    frame(&mut world);
+    assert_eq!(world.state(), GameState::Playing);
+}
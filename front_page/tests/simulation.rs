@@ -0,0 +1,811 @@
+//! Whole games run natively, without a browser or webgl.
+
+use std::rc::Rc;
+
+use nalgebra::{Point2, Vector2};
+use trunk_template::sim::{
+    lead_direction, trig, Action, ButtonState, ControlScheme, DrawMode, GameState, Model, Owner,
+    PowerUpKind, Replay, ReplayError, SaucerSize, UserInput, WeaponConfig, World, CHECKPOINT_STEPS,
+    COMBO_TIME, DYING_TIME, EXTRA_LIFE_SCORE, FIXED_STEP, INVULNERABLE_TIME, MAX_MULTIPLIER,
+    MAX_STEPS, VERSION,
+};
+
+const SEED: u64 = 42;
+
+//a key going down and up again between two steps
+fn tap(world: &mut World, action: Action) {
+    world.input.actions[action] = ButtonState::Pressed;
+}
+
+//what `Controls::latch` does with a key held or not
+fn hold(world: &mut World, action: Action, down: bool) {
+    world.input.actions[action] = world.input.actions[action].next(down, false);
+}
+
+fn run(world: &mut World, ms: f64) {
+    for _ in 0..(ms / FIXED_STEP) as usize {
+        world.step();
+    }
+}
+
+fn start(world: &mut World) {
+    tap(world, Action::Start);
+    world.step();
+}
+
+//a started game holding only what the test puts in it
+//the still asteroid in the corner keeps a new wave from coming
+fn scene() -> World {
+    let mut world = World::new(SEED);
+    start(&mut world);
+    world.clear_entities();
+    world.add_asteroid(Point2::new(900.0, 900.0), Vector2::zeros(), 1);
+    world
+}
+
+fn count(world: &World, model: Model) -> usize {
+    world.entities().filter(|x| x.object.model == model).count()
+}
+
+#[test]
+fn title_screen_spawns_a_wave() {
+    let mut world = World::new(SEED);
+    world.step();
+
+    assert_eq!(world.state(), GameState::Title);
+    assert!(world.player().is_none());
+    assert!(world.entities().count() > 0);
+}
+
+#[test]
+fn enter_starts_a_fresh_game() {
+    let mut world = World::new(SEED);
+    run(&mut world, 1000.0);
+    start(&mut world);
+
+    assert_eq!(world.state(), GameState::Playing);
+    assert_eq!(world.lives(), 3);
+    assert_eq!(world.score(), 0);
+    assert!(world.player().is_some());
+}
+
+#[test]
+fn pause_freezes_the_world() {
+    let mut world = World::new(SEED);
+    start(&mut world);
+
+    tap(&mut world, Action::Pause);
+    world.step();
+    assert_eq!(world.state(), GameState::Paused);
+
+    let before: Vec<Point2<f64>> = world.entities().map(|x| x.pos).collect();
+    run(&mut world, 1000.0);
+    let after: Vec<Point2<f64>> = world.entities().map(|x| x.pos).collect();
+    assert_eq!(before, after);
+
+    tap(&mut world, Action::Pause);
+    world.step();
+    assert_eq!(world.state(), GameState::Playing);
+}
+
+#[test]
+fn big_frames_are_split_in_fixed_steps() {
+    let mut world = World::new(SEED);
+    world.advance(FIXED_STEP * 2.5);
+    assert!((world.alpha() - 0.5).abs() < 1e-9);
+
+    //a very long frame only simulates a bounded amount of time
+    let mut steps = 0;
+    world.advance_with(60_000.0, |_| steps += 1);
+    assert_eq!(steps, MAX_STEPS);
+    //the rest of the frame is dropped, not carried over to the next ones
+    assert!(world.alpha() < 1.0);
+    let mut steps = 0;
+    world.advance_with(0.0, |_| steps += 1);
+    assert_eq!(steps, 0);
+}
+
+//keeps firing at the closest asteroid
+fn autopilot(world: &mut World) {
+    let Some(player) = world.player() else {
+        return;
+    };
+    let center = player.get_pos_center();
+
+    let target = world
+        .entities()
+        .filter(|x| x.object.model == Model::Asteroid)
+        .map(|x| x.get_pos_center())
+        .min_by(|a, b| (a - center).norm().total_cmp(&(b - center).norm()));
+
+    hold(world, Action::Fire, target.is_some());
+    if let Some(target) = target {
+        world.input.pointer = target;
+    }
+}
+
+#[test]
+fn shooting_at_asteroids_scores_points() {
+    let mut world = World::new(SEED);
+    start(&mut world);
+
+    for _ in 0..(60_000.0 / FIXED_STEP) as usize {
+        if world.score() > 0 {
+            break;
+        }
+        autopilot(&mut world);
+        world.step();
+    }
+
+    assert!(world.score() > 0);
+}
+
+//an asteroid put right under a player bullet, gone (or split) after the step
+fn shoot(world: &mut World, size: u32) {
+    let center = Point2::new(-400.0, 400.0);
+    world.add_asteroid(center, Vector2::zeros(), size);
+    world.add_bullet(center, Vector2::zeros(), Owner::Player);
+    world.step();
+}
+
+#[test]
+fn smaller_asteroids_are_worth_more() {
+    for (size, points) in [(3, 20), (2, 50), (1, 100)] {
+        let mut world = scene();
+        shoot(&mut world, size);
+        assert_eq!(world.score(), points, "size {}", size);
+    }
+}
+
+#[test]
+fn quick_kills_raise_the_multiplier() {
+    let mut world = scene();
+    let mut score = 0;
+    for multiplier in 1..=MAX_MULTIPLIER + 2 {
+        shoot(&mut world, 1);
+        let multiplier = multiplier.min(MAX_MULTIPLIER);
+        score += 100 * multiplier;
+        assert_eq!(world.multiplier(), multiplier);
+        assert_eq!(world.score(), score);
+    }
+
+    //too long without a kill and it starts over
+    run(&mut world, COMBO_TIME + FIXED_STEP);
+    assert_eq!(world.multiplier(), 1);
+    shoot(&mut world, 1);
+    assert_eq!(world.score(), score + 100);
+}
+
+#[test]
+fn every_10000_points_give_a_life() {
+    let mut world = scene();
+    while world.score() < 2 * EXTRA_LIFE_SCORE {
+        shoot(&mut world, 1);
+        assert_eq!(world.lives(), 3 + world.score() / EXTRA_LIFE_SCORE);
+    }
+    assert_eq!(world.lives(), 5);
+}
+
+fn bullets(world: &World) -> usize {
+    world
+        .entities()
+        .filter(|x| x.object.model == Model::Bullet)
+        .count()
+}
+
+#[test]
+fn weapon_limits_the_bullets() {
+    let mut world = World::new(SEED);
+    world.weapon = WeaponConfig {
+        cooldown: 100.0,
+        bullet_ttl: 500.0,
+        max_bullets: 3,
+        bullet_speed: 1.0,
+    };
+    start(&mut world);
+
+    world.input.pointer = Point2::new(0.0, 900.0);
+    hold(&mut world, Action::Fire, true);
+    world.step();
+    run(&mut world, 50.0);
+    assert_eq!(bullets(&world), 1);
+
+    run(&mut world, 1000.0);
+    assert!(bullets(&world) <= 3);
+
+    //bullets wrap around the map and vanish once their time is over
+    hold(&mut world, Action::Fire, false);
+    run(&mut world, 500.0);
+    assert_eq!(bullets(&world), 0);
+}
+
+#[test]
+fn thrust_and_death_emit_particles() {
+    let mut world = World::new(SEED);
+    start(&mut world);
+    assert!(world.particles().is_empty());
+
+    world.input.pointer = Point2::new(0.0, 900.0);
+    hold(&mut world, Action::Thrust, true);
+    run(&mut world, 100.0);
+    assert!(world
+        .particles()
+        .iter()
+        .all(|x| x.draw_mode == DrawMode::Points));
+    assert!(!world.particles().is_empty());
+
+    hold(&mut world, Action::Thrust, false);
+    while world.state() != GameState::Dying {
+        world.step();
+    }
+    assert!(world
+        .particles()
+        .iter()
+        .any(|x| x.draw_mode == DrawMode::Lines));
+    //the wreck is the biggest explosion so far
+    let wreck = world.explosions().last().unwrap();
+    assert!(world
+        .explosions()
+        .iter()
+        .all(|x| x.strength <= wreck.strength));
+
+    //they only last until the next frame
+    world.advance(FIXED_STEP);
+    assert!(world.explosions().is_empty());
+}
+
+#[test]
+fn destroyed_asteroids_drop_power_ups() {
+    let mut world = World::new(SEED);
+    start(&mut world);
+
+    let mut dropped = false;
+    for _ in 0..(120_000.0 / FIXED_STEP) as usize {
+        if world.state() == GameState::GameOver {
+            break;
+        }
+        autopilot(&mut world);
+        world.step();
+        if world.entities().any(|x| x.object.model == Model::PowerUp) {
+            dropped = true;
+            break;
+        }
+    }
+    assert!(dropped);
+}
+
+//a pickup dropped right on the ship
+fn pick_up(world: &mut World, kind: PowerUpKind) {
+    world.add_power_up(Point2::origin(), kind);
+    world.step();
+}
+
+//bullets fired holding the trigger for `ms`, once the earlier ones are gone
+fn fire_for(world: &mut World, ms: f64) -> usize {
+    run(world, world.weapon.bullet_ttl);
+    world.input.pointer = Point2::new(0.0, 500.0);
+    hold(world, Action::Fire, true);
+    run(world, ms);
+    hold(world, Action::Fire, false);
+    bullets(world)
+}
+
+#[test]
+fn spread_fires_three_bullets_until_it_runs_out() {
+    let mut world = scene();
+    pick_up(&mut world, PowerUpKind::Spread);
+    assert!(world.has_effect(PowerUpKind::Spread));
+    assert_eq!(fire_for(&mut world, FIXED_STEP), 3);
+
+    run(
+        &mut world,
+        PowerUpKind::Spread.duration().unwrap() + FIXED_STEP,
+    );
+    assert!(!world.has_effect(PowerUpKind::Spread));
+    assert_eq!(fire_for(&mut world, FIXED_STEP), 1);
+}
+
+#[test]
+fn rapid_fire_shortens_the_cooldown_until_it_runs_out() {
+    let mut world = scene();
+    let cooldown = world.weapon.cooldown;
+    pick_up(&mut world, PowerUpKind::RapidFire);
+    assert!(world.has_effect(PowerUpKind::RapidFire));
+    assert_eq!(fire_for(&mut world, cooldown - FIXED_STEP), 3);
+
+    run(
+        &mut world,
+        PowerUpKind::RapidFire.duration().unwrap() + FIXED_STEP,
+    );
+    assert!(!world.has_effect(PowerUpKind::RapidFire));
+    assert_eq!(fire_for(&mut world, cooldown - FIXED_STEP), 1);
+}
+
+#[test]
+fn shield_absorbs_one_hit() {
+    let mut world = scene();
+    run(&mut world, INVULNERABLE_TIME);
+    pick_up(&mut world, PowerUpKind::Shield);
+    assert!(world.has_effect(PowerUpKind::Shield));
+
+    world.add_asteroid(Point2::origin(), Vector2::zeros(), 3);
+    world.step();
+    assert_eq!(world.state(), GameState::Playing);
+    assert_eq!(world.lives(), 3);
+    assert!(!world.has_effect(PowerUpKind::Shield));
+
+    //the asteroid is still there, once the short grace is over it takes the ship
+    run(&mut world, 1500.0);
+    assert_eq!(world.state(), GameState::Dying);
+    assert_eq!(world.lives(), 2);
+}
+
+#[test]
+fn shield_runs_out() {
+    let mut world = scene();
+    pick_up(&mut world, PowerUpKind::Shield);
+    run(
+        &mut world,
+        PowerUpKind::Shield.duration().unwrap() + FIXED_STEP,
+    );
+    assert!(!world.has_effect(PowerUpKind::Shield));
+    assert_eq!(world.lives(), 3);
+}
+
+#[test]
+fn extra_life_is_applied_at_once() {
+    let mut world = scene();
+    pick_up(&mut world, PowerUpKind::ExtraLife);
+    assert_eq!(world.lives(), 4);
+    assert!(world.effects().is_empty());
+}
+
+#[test]
+fn saucers_show_up_while_playing() {
+    let mut world = World::new(SEED);
+    start(&mut world);
+
+    let mut seen = false;
+    for _ in 0..(60_000.0 / FIXED_STEP) as usize {
+        if world.state() == GameState::GameOver {
+            break;
+        }
+        autopilot(&mut world);
+        world.step();
+        if world.entities().any(|x| x.object.model == Model::Saucer) {
+            seen = true;
+            break;
+        }
+    }
+    assert!(seen);
+}
+
+#[test]
+fn small_saucer_leads_its_target() {
+    let from = Point2::new(0.0, 0.0);
+    let target = Point2::new(300.0, 0.0);
+    let target_speed = Vector2::new(0.0, 0.2);
+    let bullet_speed = 0.6;
+
+    let dir = lead_direction(from, target, target_speed, bullet_speed);
+    assert!((dir.norm() - 1.0).abs() < 1e-9);
+
+    //the bullet and the target reach the same place at the same time
+    let time = 300.0 / (dir.x * bullet_speed);
+    let bullet = from + dir * bullet_speed * time;
+    let target = target + target_speed * time;
+    assert!((bullet - target).norm() < 1e-6);
+
+    //a target that can't be reached is aimed at directly
+    let dir = lead_direction(from, target, Vector2::new(5.0, 0.0), bullet_speed);
+    assert!((dir - (target - from).normalize()).norm() < 1e-9);
+
+    //a target on the shooter still gives a usable direction
+    let dir = lead_direction(from, from, target_speed, bullet_speed);
+    assert!((dir.norm() - 1.0).abs() < 1e-9);
+    let dir = lead_direction(from, from, Vector2::zeros(), bullet_speed);
+    assert!((dir.norm() - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn small_saucer_aims_across_the_edge() {
+    let mut world = scene();
+    world.place_player(Point2::new(-950.0, 0.0));
+    world.add_saucer(Point2::new(950.0, 0.0), Vector2::zeros(), SaucerSize::Small);
+
+    while count(&world, Model::Bullet) == 0 {
+        world.step();
+    }
+
+    //the ship is 100 units away to the right, past the edge, not 1900 to the left
+    let bullet = world
+        .entities()
+        .find(|x| x.object.model == Model::Bullet)
+        .unwrap();
+    assert!(bullet.speed.x > 0.0);
+}
+
+#[test]
+fn shooting_a_saucer_scores_its_points() {
+    let mut world = scene();
+    let center = Point2::new(400.0, 0.0);
+    world.add_saucer(center, Vector2::zeros(), SaucerSize::Small);
+    world.add_bullet(center, Vector2::zeros(), Owner::Player);
+    world.step();
+
+    assert_eq!(count(&world, Model::Saucer), 0);
+    assert_eq!(world.score(), SaucerSize::Small.points());
+}
+
+#[test]
+fn saucer_bullets_kill_the_ship() {
+    let mut world = scene();
+    run(&mut world, INVULNERABLE_TIME);
+    world.add_bullet(Point2::origin(), Vector2::zeros(), Owner::Saucer);
+    world.step();
+
+    assert_eq!(world.state(), GameState::Dying);
+    assert_eq!(world.lives(), 2);
+    assert_eq!(count(&world, Model::Bullet), 0);
+}
+
+#[test]
+fn saucer_bullets_break_asteroids() {
+    let mut world = scene();
+    let center = Point2::new(-400.0, 0.0);
+    world.add_asteroid(center, Vector2::zeros(), 3);
+    world.add_bullet(center, Vector2::zeros(), Owner::Saucer);
+    world.step();
+
+    //two halves plus the one in the corner, but no points for the saucer's kill
+    assert_eq!(count(&world, Model::Asteroid), 3);
+    assert_eq!(world.score(), 0);
+}
+
+#[test]
+fn hyperspace_moves_the_ship_and_cools_down() {
+    //with this seed the first jump lands safely
+    let mut world = World::new(SEED);
+    start(&mut world);
+
+    tap(&mut world, Action::Hyperspace);
+    world.step();
+    assert!(world.hyperspace_cooldown() > 0.0);
+    assert_eq!(world.state(), GameState::Playing);
+
+    let player = world.player().unwrap();
+    let pos = player.pos;
+    assert!(player.get_pos_center().coords.norm() > 1.0);
+    assert_eq!(player.speed, Vector2::zeros());
+
+    //a second jump has to wait for the cooldown
+    tap(&mut world, Action::Hyperspace);
+    world.step();
+    assert_eq!(world.player().unwrap().pos, pos);
+}
+
+#[test]
+fn hyperspace_can_kill_the_ship() {
+    //with this seed the first jump goes wrong
+    let mut world = World::new(2);
+    start(&mut world);
+
+    tap(&mut world, Action::Hyperspace);
+    world.step();
+    assert_eq!(world.state(), GameState::Dying);
+    assert_eq!(world.lives(), 2);
+    assert!(world.player().is_none());
+}
+
+#[test]
+fn energy_shield_drains_and_recharges() {
+    let mut world = World::new(SEED);
+    start(&mut world);
+
+    hold(&mut world, Action::Shield, true);
+    run(&mut world, 1500.0);
+    assert!(world.shield_up());
+    assert!((world.energy() - 0.5).abs() < 0.01);
+
+    //runs out and stays down for a while even with the key held
+    run(&mut world, 2000.0);
+    assert!(!world.shield_up());
+    assert_eq!(world.energy(), 0.0);
+
+    hold(&mut world, Action::Shield, false);
+    run(&mut world, 1000.0);
+    assert_eq!(world.energy(), 0.0);
+    run(&mut world, 2000.0);
+    assert!(world.energy() > 0.0);
+}
+
+#[test]
+fn keyboard_scheme_rotates_thrusts_and_fires() {
+    let mut world = World::new(SEED);
+    world.input.scheme = ControlScheme::Keyboard;
+    start(&mut world);
+
+    //a quarter turn to the right, the nose now points along +x
+    hold(&mut world, Action::RotateRight, true);
+    while world.player().unwrap().rotation < std::f64::consts::FRAC_PI_2 {
+        world.step();
+    }
+    hold(&mut world, Action::RotateRight, false);
+
+    hold(&mut world, Action::Thrust, true);
+    hold(&mut world, Action::Fire, true);
+    world.step();
+    let speed = world.player().unwrap().speed;
+    assert!(speed.x > 0.0);
+    assert!(speed.y.abs() < speed.x * 0.1);
+
+    let bullet = world
+        .entities()
+        .find(|x| x.object.model == Model::Bullet)
+        .unwrap();
+    assert!(bullet.speed.x > 0.0);
+    assert!(bullet.speed.y.abs() < bullet.speed.x * 0.1);
+}
+
+#[test]
+fn idle_ship_runs_out_of_lives() {
+    let mut world = World::new(SEED);
+    start(&mut world);
+
+    //at most half an hour of game time
+    for _ in 0..(30.0 * 60_000.0 / FIXED_STEP) as usize {
+        if world.state() == GameState::GameOver {
+            break;
+        }
+        world.step();
+    }
+
+    assert_eq!(world.state(), GameState::GameOver);
+    assert_eq!(world.lives(), 0);
+    assert!(world.player().is_none());
+}
+
+//a still asteroid right on the ship once it can be hit
+fn crash(world: &mut World) {
+    run(world, INVULNERABLE_TIME);
+    world.add_asteroid(Point2::origin(), Vector2::zeros(), 3);
+    world.step();
+    assert_eq!(world.state(), GameState::Dying);
+}
+
+#[test]
+fn ship_respawns_once_the_centre_is_clear() {
+    let mut world = scene();
+    crash(&mut world);
+
+    //the asteroid that hit it still sits where the ship comes back
+    run(&mut world, 2.0 * DYING_TIME);
+    assert_eq!(world.state(), GameState::Dying);
+    assert!(world.player().is_none());
+
+    world.clear_entities();
+    world.add_asteroid(Point2::new(900.0, 900.0), Vector2::zeros(), 1);
+    world.step();
+    assert_eq!(world.state(), GameState::Playing);
+    assert_eq!(world.lives(), 2);
+}
+
+#[test]
+fn respawned_ship_is_invulnerable_for_a_while() {
+    let mut world = scene();
+    crash(&mut world);
+    world.clear_entities();
+    world.add_asteroid(Point2::new(900.0, 900.0), Vector2::zeros(), 1);
+    //back less than 100ms ago
+    run(&mut world, DYING_TIME + 100.0);
+    assert_eq!(world.state(), GameState::Playing);
+
+    world.add_asteroid(Point2::origin(), Vector2::zeros(), 3);
+    run(&mut world, INVULNERABLE_TIME - 200.0);
+    assert_eq!(world.state(), GameState::Playing);
+    assert_eq!(world.lives(), 2);
+
+    run(&mut world, 300.0);
+    assert_eq!(world.state(), GameState::Dying);
+    assert_eq!(world.lives(), 1);
+}
+
+#[test]
+fn same_seed_same_game() {
+    let positions = |seed: u64| {
+        let mut world = World::new(seed);
+        start(&mut world);
+        world.input.pointer = Point2::new(300.0, 200.0);
+        hold(&mut world, Action::Fire, true);
+        run(&mut world, 1500.0);
+        world
+            .entities()
+            .map(|x| (x.pos, x.rotation))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(positions(SEED), positions(SEED));
+    assert_ne!(positions(SEED), positions(SEED + 1));
+}
+
+#[test]
+fn run_does_not_depend_on_title_screen_time() {
+    let mut a = World::new(SEED);
+    start(&mut a);
+
+    let mut b = World::new(SEED);
+    run(&mut b, 3000.0);
+    start(&mut b);
+
+    run(&mut a, 1000.0);
+    run(&mut b, 1000.0);
+    let a: Vec<_> = a.entities().map(|x| x.pos).collect();
+    let b: Vec<_> = b.entities().map(|x| x.pos).collect();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn portable_trig_matches_std() {
+    for i in -2000..=2000 {
+        let x = i as f64 * 0.01;
+        assert!((trig::sin(x) - x.sin()).abs() < 1e-14, "sin {}", x);
+        assert!((trig::cos(x) - x.cos()).abs() < 1e-14, "cos {}", x);
+
+        let (y, x) = (x.sin() * 3.0, x.cos() * 2.0);
+        assert!(
+            (trig::atan2(y, x) - y.atan2(x)).abs() < 1e-14,
+            "atan2 {} {}",
+            y,
+            x
+        );
+    }
+    for i in -100..=100 {
+        let x = i as f64 * 0.01;
+        assert!((trig::acos(x) - x.acos()).abs() < 1e-14, "acos {}", x);
+    }
+}
+
+//some made up but busy input: flying around, shooting and pausing once
+fn scripted_run(world: &mut World, steps: usize) {
+    for i in 0..steps {
+        let t = i as f64 / 60.0;
+        world.input.pointer = Point2::new(t.cos() * 600.0, t.sin() * 600.0);
+        hold(world, Action::Thrust, i % 240 < 60);
+        hold(world, Action::Fire, i % 20 == 0);
+        if i == 100 || i == 160 {
+            tap(world, Action::Pause);
+        }
+        world.step();
+    }
+}
+
+fn snapshot(world: &World) -> (u32, u32, Vec<Point2<f64>>) {
+    let entities = world.entities().map(|x| x.pos).collect();
+    (world.score(), world.lives(), entities)
+}
+
+#[test]
+fn replay_rebuilds_the_run() {
+    let mut world = World::new(SEED);
+    start(&mut world);
+    scripted_run(&mut world, 1200);
+
+    let json = world.replay().to_json();
+    let mut playback = World::from_replay(Replay::from_json(&json).unwrap());
+    while !playback.playback_finished() {
+        playback.step();
+    }
+
+    assert_eq!(playback.playback().unwrap().tick(), 1200);
+    assert_eq!(snapshot(&playback), snapshot(&world));
+}
+
+#[test]
+fn seek_lands_on_the_same_run() {
+    let mut world = World::new(SEED);
+    start(&mut world);
+    scripted_run(&mut world, 1500);
+
+    //the first seek saves checkpoints on the way, the next ones start from them
+    let mut playback = World::from_replay(world.replay().clone());
+    playback.seek(1300);
+    assert_eq!(playback.playback().unwrap().tick(), 1300);
+    playback.seek(700);
+    assert_eq!(playback.playback().unwrap().tick(), 700);
+    playback.seek(900);
+    playback.seek(200);
+    while !playback.playback_finished() {
+        playback.step();
+    }
+
+    assert_eq!(snapshot(&playback), snapshot(&world));
+}
+
+//a ship turning around and shooting, built without playing it
+fn long_replay(steps: usize) -> Replay {
+    let mut replay = Replay::new(SEED);
+    let mut input = UserInput::default();
+    for i in 0..steps {
+        let t = i as f64 / 100.0;
+        input.pointer = Point2::new(t.cos() * 600.0, t.sin() * 600.0);
+        input.actions[Action::Fire] = if i % 30 == 0 {
+            ButtonState::Pressed
+        } else {
+            ButtonState::None
+        };
+        replay.push(&input);
+    }
+    replay
+}
+
+#[test]
+fn seeking_a_long_replay_shares_it() {
+    let steps = 20_000;
+    let mut playback = World::from_replay(long_replay(steps));
+    playback.seek(steps - 1);
+    assert_eq!(playback.playback().unwrap().tick(), steps - 1);
+
+    //every checkpoint points to the same replay instead of holding a copy
+    let checkpoints = (steps - 1) / CHECKPOINT_STEPS;
+    assert_eq!(
+        Rc::strong_count(playback.playback().unwrap().replay()),
+        checkpoints + 1
+    );
+
+    let end = snapshot(&playback);
+    playback.seek(CHECKPOINT_STEPS + 10);
+    assert_eq!(
+        Rc::strong_count(playback.playback().unwrap().replay()),
+        checkpoints + 1
+    );
+    playback.seek(steps - 1);
+    assert_eq!(snapshot(&playback), end);
+}
+
+#[test]
+fn long_replays_stay_small() {
+    //the pointer changes every step, ten minutes have to fit in the localStorage quota
+    let steps = (10.0 * 60_000.0 / FIXED_STEP) as usize;
+    let replay = long_replay(steps);
+    let json = replay.to_json();
+    assert!(json.len() < steps * 6, "{} bytes", json.len());
+    assert_eq!(Replay::from_json(&json).unwrap(), replay);
+
+    let broken = json.replace("\"frames\":\"", "\"frames\":\"!");
+    assert!(matches!(
+        Replay::from_json(&broken),
+        Err(ReplayError::Json(_))
+    ));
+}
+
+#[test]
+fn replays_from_other_versions_are_refused() {
+    let json = Replay::new(SEED).to_json().replace(
+        &format!("\"version\":{}", VERSION),
+        &format!("\"version\":{}", VERSION - 1),
+    );
+    assert!(matches!(
+        Replay::from_json(&json),
+        Err(ReplayError::Version(_))
+    ));
+}
+
+#[test]
+fn saucers_leave_once_the_game_is_over() {
+    //with this seed the idle ship is shot down while a saucer is on the screen
+    let mut world = World::new(1);
+    start(&mut world);
+    for _ in 0..(10.0 * 60_000.0 / FIXED_STEP) as usize {
+        if world.state() == GameState::GameOver {
+            break;
+        }
+        world.step();
+    }
+    let saucer = |world: &World| world.entities().any(|x| x.object.model == Model::Saucer);
+    assert_eq!(world.state(), GameState::GameOver);
+    assert!(saucer(&world));
+
+    //long enough to cross the whole map
+    run(&mut world, 20_000.0);
+    assert!(!saucer(&world));
+}